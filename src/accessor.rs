@@ -0,0 +1,363 @@
+//! Accessor property code generation (`#[glue_v8::getter]` / `#[glue_v8::setter]`).
+//!
+//! Accessors are native data properties backed by Rust functions:
+//! - Getter: `AccessorNameGetterCallback` (scope, key, args, rv)
+//! - Setter: `AccessorNameSetterCallback` (scope, key, value, args, rv)
+//!
+//! State is passed via the accessor data (External pointing to the state), read
+//! with `args.data()` exactly like FunctionTemplate data. The getter and setter
+//! of a property share the data set on the `AccessorConfiguration`, so the
+//! getter names its setter (`setter = path`) and chains it in its configuration
+//! helper, after checking at compile time that both declare the same state type.
+
+use quote::{quote, quote_spanned};
+use syn::spanned::Spanned;
+use syn::{ItemFn, ReturnType};
+
+use crate::codegen::{
+    generate_call_and_return, generate_call_args, generate_registry_state_extractions,
+    generate_state_external, generate_state_extraction, generate_value_extraction,
+    return_conversion, state_call_arg, state_param_type,
+};
+use crate::parse::{MethodAttrs, OpParams, StateMode, strip_param_markers};
+use crate::snapshot::generate_accessor_external_refs;
+use crate::types::{StateKind, is_result_type};
+
+/// Check the attributes and signature of an accessor.
+///
+/// Only `state = Type` and `int64 = ..` (plus `setter = path` on getters) apply
+/// to accessors; the first other key is reported at its span. Accessors are
+/// synchronous and take either the scope or the isolate.
+fn check_accessor(
+    attrs: &MethodAttrs,
+    sig: &syn::Signature,
+    op: &OpParams,
+    macro_name: &str,
+) -> Result<(), syn::Error> {
+    let is_getter = macro_name == "getter";
+    // `state(slot = ..)` parses as a `state` key too
+    let unsupported = attrs.keys.iter().find(|(key, _)| match key.as_str() {
+        "state" => attrs.state_mode.is_slot(),
+        "int64" => false,
+        "setter" => !is_getter,
+        _ => true,
+    });
+
+    if let Some((_, span)) = unsupported {
+        let supported = if is_getter {
+            "`state = Type`, `int64 = ..` and `setter = path`"
+        } else {
+            "`state = Type` and `int64 = ..`"
+        };
+        return Err(syn::Error::new(
+            *span,
+            format!("glue_v8::{} only supports {}", macro_name, supported),
+        ));
+    }
+
+    if let Some(asyncness) = &sig.asyncness {
+        return Err(syn::Error::new_spanned(
+            asyncness,
+            format!(
+                "{}s cannot be `async fn` (V8 needs the result when the property is accessed)",
+                macro_name
+            ),
+        ));
+    }
+
+    if op.has_scope && op.has_isolate {
+        return Err(syn::Error::new_spanned(
+            &sig.inputs,
+            format!(
+                "{}s take either the scope or the isolate, not both (the isolate is borrowed from the scope)",
+                macro_name
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+/// `path::to::item` with `suffix` appended to its last segment
+fn suffixed_path(path: &syn::Path, suffix: &str) -> syn::Path {
+    let mut path = path.clone();
+    let last = path.segments.last_mut().unwrap();
+    last.ident = syn::Ident::new(&format!("{}{}", last.ident, suffix), last.ident.span());
+    path
+}
+
+/// Name of the identity fn over the state a setter reads (`{fn}_v8_setter_state`)
+fn setter_state_check(path: &syn::Path) -> syn::Path {
+    suffixed_path(path, "_v8_setter_state")
+}
+
+/// Declared state type of an accessor, `()` without state
+fn declared_state_type(attrs: &MethodAttrs) -> proc_macro2::TokenStream {
+    match &attrs.state_type {
+        Some(state_ty) => quote!(#state_ty),
+        None => quote!(()),
+    }
+}
+
+/// State extraction code, and the arguments passed to the user function (in
+/// declaration order)
+fn special_call_args(
+    attrs: &MethodAttrs,
+    op: &OpParams,
+) -> syn::Result<(proc_macro2::TokenStream, Vec<proc_macro2::TokenStream>)> {
+    let state_extraction =
+        generate_state_extraction(op, attrs.state_type.as_ref(), attrs.state_mode)?;
+    let (registry_extractions, registry_call_args) =
+        generate_registry_state_extractions(&op.states)?;
+    let state_arg = state_call_arg(attrs.state_type.as_ref(), false, op.state_mut);
+    let args = generate_call_args(op, &state_arg, &registry_call_args);
+
+    let state_extraction = quote! {
+        #state_extraction
+        #(#registry_extractions)*
+    };
+
+    Ok((state_extraction, args))
+}
+
+/// Generate a getter callback and its `{fn}_v8_accessor` configuration helper.
+pub fn generate_getter(attrs: MethodAttrs, input_fn: &ItemFn) -> proc_macro2::TokenStream {
+    let fn_name = &input_fn.sig.ident;
+    let getter_name = syn::Ident::new(&format!("{}_v8_getter", fn_name), fn_name.span());
+    let accessor_fn_name = syn::Ident::new(&format!("{}_v8_accessor", fn_name), fn_name.span());
+
+    let op = match OpParams::from_signature(&input_fn.sig, Some(&attrs)) {
+        Ok(op) => op,
+        Err(err) => return err.to_compile_error(),
+    };
+    if let Err(err) = check_accessor(&attrs, &input_fn.sig, &op, "getter") {
+        return err.to_compile_error();
+    }
+    let mut input_fn = input_fn.clone();
+    strip_param_markers(&mut input_fn.sig);
+
+    if !op.params.is_empty() {
+        return syn::Error::new_spanned(
+            &input_fn.sig.inputs,
+            "getters only take `scope` and `state` parameters",
+        )
+        .to_compile_error();
+    }
+
+    let returns_result = match &input_fn.sig.output {
+        ReturnType::Type(_, ty) => is_result_type(ty),
+        ReturnType::Default => {
+            return syn::Error::new_spanned(&input_fn.sig, "getters must return a value")
+                .to_compile_error();
+        }
+    };
+
+    let (state_extraction, call_args) = match special_call_args(&attrs, &op) {
+        Ok(v) => v,
+        Err(err) => return err.to_compile_error(),
+    };
+    let call_and_return = generate_call_and_return(
+        &quote!(#fn_name),
+        &call_args,
+        true,
+        returns_result,
+        false,
+        return_conversion(&input_fn.sig.output, attrs.int64),
+        &fn_name.to_string(),
+    );
+    let accessor_fn = generate_accessor_configuration(
+        &getter_name,
+        &accessor_fn_name,
+        op.has_state,
+        attrs.state_type.as_ref(),
+        attrs.setter.as_ref(),
+    );
+
+    let external_refs =
+        generate_accessor_external_refs(fn_name, &getter_name, &quote!(AccessorNameGetterCallback));
+
+    // A setter with state reads the accessor data as its own state type: both
+    // must match (the check of a stateless setter is generic, and accepts any)
+    let setter_check = attrs.setter.as_ref().map(|setter| {
+        let getter_state = declared_state_type(&attrs);
+        let setter_state = setter_state_check(setter);
+        quote_spanned! {setter.span()=>
+            const _: fn(#getter_state) -> #getter_state = #setter_state;
+        }
+    });
+
+    quote! {
+        #input_fn
+
+        /// V8 accessor getter callback - auto-generated by glue_v8::getter
+        pub fn #getter_name(
+            __v8g_scope: &mut v8::PinScope,
+            _key: v8::Local<v8::Name>,
+            __v8g_args: v8::PropertyCallbackArguments,
+            mut __v8g_rv: v8::ReturnValue,
+        ) {
+            #state_extraction
+            #call_and_return
+        }
+
+        #accessor_fn
+
+        #external_refs
+
+        #setter_check
+    }
+}
+
+/// Generate a setter callback.
+///
+/// The JS value is converted like an op argument (see `generate_value_extraction`).
+pub fn generate_setter(attrs: MethodAttrs, input_fn: &ItemFn) -> proc_macro2::TokenStream {
+    let fn_name = &input_fn.sig.ident;
+    let setter_name = syn::Ident::new(&format!("{}_v8_setter", fn_name), fn_name.span());
+    let state_check = setter_state_check(&fn_name.clone().into());
+
+    let op = match OpParams::from_signature(&input_fn.sig, Some(&attrs)) {
+        Ok(op) => op,
+        Err(err) => return err.to_compile_error(),
+    };
+    if let Err(err) = check_accessor(&attrs, &input_fn.sig, &op, "setter") {
+        return err.to_compile_error();
+    }
+    let mut input_fn = input_fn.clone();
+    strip_param_markers(&mut input_fn.sig);
+
+    let [(name, ty)] = op.params.as_slice() else {
+        return syn::Error::new_spanned(
+            &input_fn.sig.inputs,
+            "setters take exactly one value parameter (besides `scope` and `state`)",
+        )
+        .to_compile_error();
+    };
+
+    let (state_extraction, call_args) = match special_call_args(&attrs, &op) {
+        Ok(v) => v,
+        Err(err) => return err.to_compile_error(),
+    };
+    let state_check_fn = match &attrs.state_type {
+        Some(state_ty) => quote! {
+            pub fn #state_check(state: #state_ty) -> #state_ty {
+                state
+            }
+        },
+        None => quote! {
+            pub fn #state_check<S>(state: S) -> S {
+                state
+            }
+        },
+    };
+    let external_refs =
+        generate_accessor_external_refs(fn_name, &setter_name, &quote!(AccessorNameSetterCallback));
+    let value_extraction =
+        generate_value_extraction(name, ty, &quote!(__v8g_value), "value", attrs.int64);
+
+    // Setter return values are ignored by V8, only Err is surfaced (as an exception)
+    let returns_result = match &input_fn.sig.output {
+        ReturnType::Type(_, ty) => is_result_type(ty),
+        ReturnType::Default => false,
+    };
+    let call = if returns_result {
+        quote! {
+            if let Err(err) = #fn_name(#(#call_args),*) {
+                let err_str = format!("{}", err);
+                let msg = v8::String::new(__v8g_scope, &err_str).unwrap();
+                let error = v8::Exception::error(__v8g_scope, msg);
+                __v8g_scope.throw_exception(error);
+            }
+        }
+    } else {
+        quote! {
+            let _ = #fn_name(#(#call_args),*);
+        }
+    };
+    quote! {
+        #input_fn
+
+        /// V8 accessor setter callback - auto-generated by glue_v8::setter
+        pub fn #setter_name(
+            __v8g_scope: &mut v8::PinScope,
+            _key: v8::Local<v8::Name>,
+            __v8g_value: v8::Local<v8::Value>,
+            __v8g_args: v8::PropertyCallbackArguments,
+            __v8g_rv: v8::ReturnValue<()>,
+        ) {
+            #state_extraction
+            #value_extraction
+            #call
+        }
+
+        #external_refs
+
+        /// Identity over the state this setter reads from the accessor data, checked
+        /// by the getter's `setter = ..` - auto-generated by glue_v8::setter
+        #[doc(hidden)]
+        #state_check_fn
+    }
+}
+
+/// Generate the `{fn}_v8_accessor` helper returning an AccessorConfiguration for the getter,
+/// and its `setter = ..` if any.
+///
+/// With state, the configuration carries an External pointing to the state as data
+/// (plain `T` state is owned by it, see `generate_state_external`).
+fn generate_accessor_configuration(
+    getter_name: &syn::Ident,
+    accessor_fn_name: &syn::Ident,
+    has_state: bool,
+    state_type: Option<&syn::Type>,
+    setter: Option<&syn::Path>,
+) -> proc_macro2::TokenStream {
+    // The setter may read the state even when the getter does not
+    let has_state = has_state || setter.is_some();
+    let setter = setter.map(|setter| {
+        let setter_fn = suffixed_path(setter, "_v8_setter");
+        quote!(.setter(#setter_fn))
+    });
+
+    let Some(state_type) = state_type.filter(|_| has_state) else {
+        return quote! {
+            /// Create an AccessorConfiguration for this getter (and its setter).
+            ///
+            /// # Example
+            ///
+            /// ```ignore
+            /// let key = v8::String::new(scope, "userAgent").unwrap();
+            /// template.set_accessor_with_configuration(key.into(), user_agent_v8_accessor());
+            /// ```
+            pub fn #accessor_fn_name<'s>() -> v8::AccessorConfiguration<'s> {
+                v8::AccessorConfiguration::new(#getter_name) #setter
+            }
+        };
+    };
+
+    let kind = StateKind::of(state_type);
+    let state_param = state_param_type(kind, StateMode::Borrowed);
+    let state_external = generate_state_external(kind, StateMode::Borrowed);
+
+    quote! {
+        /// Create an AccessorConfiguration for this getter (and its setter), with
+        /// state passed via External data.
+        ///
+        /// The caller must ensure the state outlives the property.
+        ///
+        /// # Example
+        ///
+        /// ```ignore
+        /// let state = Rc::new(MyState { ... });
+        /// let key = v8::String::new(scope, "url").unwrap();
+        /// template.set_accessor_with_configuration(key.into(), url_v8_accessor(scope, &state));
+        /// ```
+        pub fn #accessor_fn_name<'s>(
+            scope: &mut v8::PinScope<'s, '_>,
+            state: #state_param,
+        ) -> v8::AccessorConfiguration<'s> {
+            #state_external
+
+            v8::AccessorConfiguration::new(#getter_name) #setter .data(external.into())
+        }
+    }
+}
@@ -0,0 +1,110 @@
+//! Async op code generation.
+//!
+//! `async fn` ops return a pending Promise. The future is handed to the
+//! spawner type given with `spawn = Type`, which implements the embedder's
+//! spawner trait: `Type::spawn(future)` takes a
+//! `Pin<Box<dyn Future<Output = ()>>>`. Once it completes, its settlement (the
+//! resolver, kept as a `v8::Global`, with the output) is queued in an isolate
+//! slot shared by every async op. `glue_v8::drain!(scope)` settles the queued
+//! promises, and must be called by the event loop after polling the spawner's
+//! executor.
+//!
+//! glue_v8 has no runtime crate, so the queue is keyed by a type built from
+//! std and v8 types only: every expansion names the same slot. For the same
+//! reason the spawner trait is declared by the embedder, in scope of the ops.
+
+use quote::quote;
+
+use crate::codegen::{generate_promise_settlement, return_conversion};
+use crate::parse::Int64Repr;
+
+/// Type of the isolate slot queueing the settlements of completed async calls
+fn completion_queue_type() -> proc_macro2::TokenStream {
+    quote! {
+        std::rc::Rc<std::cell::RefCell<std::collections::VecDeque<Box<dyn FnOnce(&mut v8::PinScope)>>>>
+    }
+}
+
+/// Generate the call code for an async op (slow path wrapper body).
+///
+/// Creates the Promise, starts the future and hands it to the spawner. The
+/// spawned task queues the settlement of the promise once the future completes.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_async_call(
+    fn_name: &syn::Ident,
+    call_args: &[proc_macro2::TokenStream],
+    spawn: &syn::Type,
+    output: &syn::ReturnType,
+    has_return: bool,
+    returns_result: bool,
+    int64: Int64Repr,
+    js_name: &str,
+) -> proc_macro2::TokenStream {
+    let queue_type = completion_queue_type();
+    let settlement = generate_promise_settlement(
+        &quote!(__v8g_result),
+        has_return,
+        returns_result,
+        return_conversion(output, int64),
+        js_name,
+    );
+    let result_binding = if has_return {
+        quote!(__v8g_result)
+    } else {
+        quote!(_)
+    };
+
+    quote! {
+        let __v8g_resolver = v8::PromiseResolver::new(__v8g_scope).unwrap();
+        let __v8g_promise = __v8g_resolver.get_promise(__v8g_scope);
+        __v8g_rv.set(__v8g_promise.into());
+
+        let __v8g_queue = match __v8g_scope.get_slot::<#queue_type>() {
+            Some(queue) => queue.clone(),
+            None => {
+                let queue = <#queue_type>::default();
+                __v8g_scope.set_slot(queue.clone());
+                queue
+            }
+        };
+        let __v8g_resolver = v8::Global::new(__v8g_scope, __v8g_resolver);
+        let __v8g_future = #fn_name(#(#call_args),*);
+
+        let _ = <#spawn>::spawn(Box::pin(async move {
+            let #result_binding = __v8g_future.await;
+            let __v8g_settle: Box<dyn FnOnce(&mut v8::PinScope)> =
+                Box::new(move |__v8g_scope: &mut v8::PinScope| {
+                    let __v8g_resolver = v8::Local::new(__v8g_scope, __v8g_resolver);
+                    #settlement
+                });
+            __v8g_queue.borrow_mut().push_back(__v8g_settle);
+        }));
+    }
+}
+
+/// Expand `glue_v8::drain!(scope)`: settle the promises of every completed
+/// async call, and evaluate to the number of promises settled.
+pub fn generate_drain(scope: &syn::Expr) -> proc_macro2::TokenStream {
+    let queue_type = completion_queue_type();
+
+    quote! {
+        {
+            fn drain(scope: &mut v8::PinScope) -> usize {
+                let Some(queue) = scope.get_slot::<#queue_type>().cloned() else {
+                    return 0;
+                };
+
+                let completed = std::mem::take(&mut *queue.borrow_mut());
+                let count = completed.len();
+
+                for settle in completed {
+                    settle(scope);
+                }
+
+                count
+            }
+
+            drain(#scope)
+        }
+    }
+}
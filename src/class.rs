@@ -0,0 +1,612 @@
+//! Class bindings: Rust structs exposed as JS classes.
+//!
+//! Object layout (shared by `#[glue_v8::class]` and `#[glue_v8::methods]`):
+//! - Internal field 0: `v8::External` pointing to the per-class brand static
+//! - Internal field 1: aligned pointer to a boxed `RefCell<T>` holding the value
+//!
+//! The brand is a tagged value so it can be read from any object: objects of
+//! other classes or embedders may keep raw data in field 0, which is only safe
+//! to read back as an aligned pointer by whoever wrote it.
+//!
+//! The boxed value is owned by a guaranteed weak-handle finalizer and dropped
+//! when the JS object is garbage collected, or at the latest when the isolate
+//! is disposed.
+
+use quote::quote;
+use syn::{ImplItem, ItemImpl, ItemStruct, ReturnType, Type};
+
+use crate::codegen::{
+    generate_arg_extractions, generate_call_and_return, generate_call_args,
+    generate_guaranteed_release, return_conversion,
+};
+use crate::fast::{CallCounters, generate_fast_method};
+use crate::parse::{ClassAttrs, MethodAttrs, OpParams, ParamRole, StateMode, strip_param_markers};
+use crate::snapshot::{
+    external_refs_const_name, fast_external_refs, generate_raw_callback, raw_callback_name,
+};
+use crate::types::is_result_type;
+
+/// Number of internal fields on class instances (brand + value)
+pub const INTERNAL_FIELD_COUNT: usize = 2;
+
+/// Convert a CamelCase type name to SCREAMING_SNAKE_CASE (e.g. TextEncoder -> TEXT_ENCODER)
+pub fn screaming_snake_case(name: &str) -> String {
+    let mut out = String::new();
+
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            out.push('_');
+        }
+        out.push(c.to_ascii_uppercase());
+    }
+
+    out
+}
+
+/// Names of the items generated for a class, derived from the type name.
+struct ClassNames {
+    class_name_const: syn::Ident,
+    brand_static: syn::Ident,
+    wrap_fn: syn::Ident,
+    unwrap_fn: syn::Ident,
+    template_fn: syn::Ident,
+    new_instance_fn: syn::Ident,
+    external_refs_const: syn::Ident,
+}
+
+impl ClassNames {
+    fn new(type_ident: &syn::Ident) -> Self {
+        let upper = screaming_snake_case(&type_ident.to_string());
+        let ident = |name: String| syn::Ident::new(&name, type_ident.span());
+
+        Self {
+            class_name_const: ident(format!("{}_V8_CLASS_NAME", upper)),
+            brand_static: ident(format!("{}_V8_CLASS_BRAND", upper)),
+            wrap_fn: ident(format!("{}_v8_wrap", type_ident)),
+            unwrap_fn: ident(format!("{}_v8_unwrap", type_ident)),
+            template_fn: ident(format!("{}_v8_class_template", type_ident)),
+            new_instance_fn: ident(format!("{}_v8_new_instance", type_ident)),
+            external_refs_const: external_refs_const_name(type_ident),
+        }
+    }
+}
+
+/// Generate the storage helpers for a `#[glue_v8::class]` struct.
+pub fn generate_class(attrs: ClassAttrs, item: &ItemStruct) -> proc_macro2::TokenStream {
+    if !item.generics.params.is_empty() {
+        return syn::Error::new_spanned(&item.generics, "glue_v8::class does not support generics")
+            .to_compile_error();
+    }
+
+    let vis = &item.vis;
+    let ty = &item.ident;
+    let js_name = attrs.js_name.unwrap_or_else(|| ty.to_string());
+    let ClassNames {
+        class_name_const,
+        brand_static,
+        wrap_fn,
+        unwrap_fn,
+        ..
+    } = ClassNames::new(ty);
+    let internal_field_count = INTERNAL_FIELD_COUNT;
+    let release =
+        generate_guaranteed_release(&quote!(object), &quote!(v8::Object), &quote!(drop(value);));
+
+    quote! {
+        #item
+
+        /// JS class name - auto-generated by glue_v8::class
+        #vis const #class_name_const: &str = #js_name;
+
+        /// Brand referenced by internal field 0 of every wrapped object (its address identifies the class)
+        #[doc(hidden)]
+        #vis static #brand_static: u16 = 0;
+
+        /// Store a Rust value in a JS object created from the class template.
+        ///
+        /// The value is dropped when the object is garbage collected, or when the
+        /// isolate is disposed. Returns `false` (dropping the value) if the object
+        /// has fewer internal fields than class instances.
+        #[allow(non_snake_case)]
+        #[must_use]
+        #vis fn #wrap_fn(
+            scope: &mut v8::PinScope,
+            object: v8::Local<v8::Object>,
+            value: #ty,
+        ) -> bool {
+            // Writing past the internal fields is out of bounds in release builds
+            if object.internal_field_count() < #internal_field_count {
+                return false;
+            }
+
+            let value = Box::new(std::cell::RefCell::new(value));
+            let brand = &#brand_static as *const u16 as *mut std::ffi::c_void;
+            let brand = v8::External::new(scope, brand);
+            let ptr = &*value as *const std::cell::RefCell<#ty> as *const std::ffi::c_void;
+            object.set_internal_field(0, brand.into());
+            object.set_aligned_pointer_in_internal_field(1, ptr, 0);
+
+            // The finalizer owns the value
+            #release
+            true
+        }
+
+        /// Get the Rust value wrapped by a JS object.
+        ///
+        /// Returns `None` if the object was not created from this class.
+        #[allow(non_snake_case)]
+        #vis fn #unwrap_fn<'o>(
+            scope: &v8::PinScope,
+            object: v8::Local<'o, v8::Object>,
+        ) -> Option<&'o std::cell::RefCell<#ty>> {
+            // Field 0 is read as a tagged value, which is valid whatever the object stores there
+            let brand = object.get_internal_field(scope, 0)?;
+            let brand = v8::Local::<v8::External>::try_from(brand).ok()?;
+            if brand.value() as *const u16 != &#brand_static as *const u16 {
+                return None;
+            }
+            if object.internal_field_count() < #internal_field_count {
+                return None;
+            }
+
+            // SAFETY: the brand check guarantees the object was wrapped by this class,
+            // which stores an aligned pointer to a RefCell of this type in field 1
+            unsafe {
+                let ptr = object.get_aligned_pointer_from_internal_field(1, 0);
+                Some(&*(ptr as *const std::cell::RefCell<#ty>))
+            }
+        }
+    }
+}
+
+/// A method exported from a `#[glue_v8::methods]` impl block.
+struct ClassMethod {
+    js_name: String,
+    wrapper_name: syn::Ident,
+    /// CFunction consts of a `fast` method (empty without a Fast API path)
+    cfunction_names: Vec<syn::Ident>,
+}
+
+/// Remove glue_v8 helper attributes (`#[constructor]`, `#[method(...)]`) from a method.
+///
+/// Returns (is_constructor, method attributes).
+fn take_method_attrs(
+    attrs: &mut Vec<syn::Attribute>,
+) -> Result<(bool, Option<MethodAttrs>), syn::Error> {
+    let mut is_constructor = false;
+    let mut method_attrs = None;
+    let mut error = None;
+
+    attrs.retain(|attr| {
+        if attr.path().is_ident("constructor") {
+            is_constructor = true;
+            false
+        } else if attr.path().is_ident("method") {
+            let tokens = match &attr.meta {
+                syn::Meta::List(list) => list.tokens.clone(),
+                _ => proc_macro2::TokenStream::new(),
+            };
+            match MethodAttrs::parse(tokens.into()) {
+                Ok(parsed)
+                    if parsed.state_type.is_some()
+                        || parsed.spawn.is_some()
+                        || parsed.setter.is_some()
+                        || parsed.state_mode != StateMode::Borrowed =>
+                {
+                    error = Some(syn::Error::new_spanned(
+                        attr,
+                        "class methods support `name = \"jsName\"`, `promise`, `fast` and `int64 = ..` only",
+                    ));
+                }
+                Ok(parsed) => method_attrs = Some(parsed),
+                Err(err) => error = Some(err),
+            }
+            false
+        } else {
+            true
+        }
+    });
+
+    match error {
+        Some(err) => Err(err),
+        None => Ok((is_constructor, method_attrs)),
+    }
+}
+
+/// Generate callbacks and the class template for a `#[glue_v8::methods]` impl block.
+pub fn generate_methods(mut item: ItemImpl) -> proc_macro2::TokenStream {
+    if !item.generics.params.is_empty() || item.trait_.is_some() {
+        return syn::Error::new_spanned(
+            &item.self_ty,
+            "glue_v8::methods must be placed on an inherent impl of a non-generic type",
+        )
+        .to_compile_error();
+    }
+
+    let self_ty = item.self_ty.clone();
+    let type_ident = match &*self_ty {
+        Type::Path(type_path) if type_path.qself.is_none() => {
+            type_path.path.segments.last().unwrap().ident.clone()
+        }
+        _ => {
+            return syn::Error::new_spanned(&self_ty, "glue_v8::methods expects a struct type")
+                .to_compile_error();
+        }
+    };
+    let ClassNames {
+        class_name_const,
+        wrap_fn,
+        unwrap_fn,
+        template_fn,
+        new_instance_fn,
+        external_refs_const,
+        ..
+    } = ClassNames::new(&type_ident);
+
+    let mut constructor: Option<syn::Ident> = None;
+    let mut methods: Vec<ClassMethod> = Vec::new();
+    let mut wrappers: Vec<proc_macro2::TokenStream> = Vec::new();
+    let mut counter_statics: Vec<proc_macro2::TokenStream> = Vec::new();
+
+    for impl_item in &mut item.items {
+        let ImplItem::Fn(method) = impl_item else {
+            continue;
+        };
+
+        let (is_constructor, method_attrs) = match take_method_attrs(&mut method.attrs) {
+            Ok(v) => v,
+            Err(err) => return err.to_compile_error(),
+        };
+        let receiver = method.sig.receiver().cloned();
+
+        // Only constructors and methods with a receiver are exposed to JS
+        if !is_constructor && receiver.is_none() {
+            continue;
+        }
+
+        let fn_name = method.sig.ident.clone();
+        let wrapper_name = syn::Ident::new(&format!("{}_v8", fn_name), fn_name.span());
+        let op = match OpParams::from_signature(&method.sig, None) {
+            Ok(op) => op,
+            Err(err) => return err.to_compile_error(),
+        };
+        strip_param_markers(&mut method.sig);
+
+        if op.has_state || !op.states.is_empty() {
+            return syn::Error::new_spanned(
+                &method.sig,
+                "class methods cannot take `state`; store it in the class instead",
+            )
+            .to_compile_error();
+        }
+
+        let int64 = method_attrs
+            .as_ref()
+            .map(|attrs| attrs.int64)
+            .unwrap_or_default();
+        let arg_extractions = generate_arg_extractions(&op.params, int64);
+        let mut call_args = Vec::new();
+
+        if is_constructor {
+            if receiver.is_some() {
+                return syn::Error::new_spanned(
+                    &method.sig,
+                    "#[constructor] must be an associated function returning Self",
+                )
+                .to_compile_error();
+            }
+
+            call_args.extend(generate_call_args(&op, &quote!(), &[]));
+
+            let returns_result = match &method.sig.output {
+                ReturnType::Type(_, ty) => is_result_type(ty),
+                ReturnType::Default => false,
+            };
+            let construct = if returns_result {
+                quote! {
+                    let __v8g_value = match <#self_ty>::#fn_name(#(#call_args),*) {
+                        Ok(value) => value,
+                        Err(err) => {
+                            let err_str = format!("{}", err);
+                            let msg = v8::String::new(__v8g_scope, &err_str).unwrap();
+                            let error = v8::Exception::error(__v8g_scope, msg);
+                            __v8g_scope.throw_exception(error);
+                            return;
+                        }
+                    };
+                }
+            } else {
+                quote! {
+                    let __v8g_value = <#self_ty>::#fn_name(#(#call_args),*);
+                }
+            };
+
+            wrappers.push(quote! {
+                /// V8 constructor callback - auto-generated by glue_v8::methods
+                pub fn #wrapper_name(
+                    __v8g_scope: &mut v8::PinScope,
+                    __v8g_args: v8::FunctionCallbackArguments,
+                    __v8g_rv: v8::ReturnValue,
+                ) {
+                    if !__v8g_args.is_construct_call() {
+                        let msg = v8::String::new(
+                            __v8g_scope,
+                            &format!("Class constructor {} cannot be invoked without 'new'", #class_name_const),
+                        )
+                        .unwrap();
+                        let err = v8::Exception::type_error(__v8g_scope, msg);
+                        __v8g_scope.throw_exception(err);
+                        return;
+                    }
+
+                    #(#arg_extractions)*
+                    #construct
+                    // `this` lacks the internal fields when `new.target` is a foreign constructor
+                    if !#wrap_fn(__v8g_scope, __v8g_args.this(), __v8g_value) {
+                        let msg = v8::String::new(__v8g_scope, "Illegal invocation").unwrap();
+                        let err = v8::Exception::type_error(__v8g_scope, msg);
+                        __v8g_scope.throw_exception(err);
+                    }
+                }
+            });
+            wrappers.push(generate_raw_callback(
+                &wrapper_name,
+                &quote!(Self::#wrapper_name),
+            ));
+
+            constructor = Some(wrapper_name);
+            continue;
+        }
+
+        let receiver = receiver.unwrap();
+        if receiver.reference.is_none() {
+            return syn::Error::new_spanned(
+                &receiver,
+                "class methods must take `&self` or `&mut self`",
+            )
+            .to_compile_error();
+        }
+
+        let method_attrs = method_attrs.unwrap_or_default();
+        let borrow = if receiver.mutability.is_some() {
+            call_args.push(quote! { &mut *__v8g_self });
+            quote! { let Ok(mut __v8g_self) = __v8g_cell.try_borrow_mut() }
+        } else {
+            call_args.push(quote! { &*__v8g_self });
+            quote! { let Ok(__v8g_self) = __v8g_cell.try_borrow() }
+        };
+        call_args.extend(generate_call_args(&op, &quote!(), &[]));
+
+        let has_return = !matches!(method.sig.output, ReturnType::Default);
+        let returns_result = match &method.sig.output {
+            ReturnType::Type(_, ty) => is_result_type(ty),
+            ReturnType::Default => false,
+        };
+        let js_name = method_attrs
+            .js_name
+            .clone()
+            .unwrap_or_else(|| fn_name.to_string());
+        let call_and_return = generate_call_and_return(
+            &quote!(<#self_ty>::#fn_name),
+            &call_args,
+            has_return,
+            returns_result,
+            method_attrs.promise,
+            return_conversion(&method.sig.output, int64),
+            &format!("{}.{}", type_ident, js_name),
+        );
+        let borrow_error = format!(
+            "{}.{} re-entered while the object is borrowed",
+            type_ident, fn_name
+        );
+
+        let mut count_slow = quote!();
+        let (fast_fns, cfunction_names) = if method_attrs.fast {
+            let counters = CallCounters::new(
+                &format!(
+                    "{}_{}",
+                    screaming_snake_case(&type_ident.to_string()),
+                    fn_name.to_string().to_uppercase()
+                ),
+                fn_name.span(),
+                &op.params,
+            );
+            count_slow = counters.count_slow();
+            counter_statics.push(counters.statics());
+
+            let fast_method = generate_fast_method(
+                &method.sig,
+                &op.params,
+                // The scope and isolate params need the slow path
+                op.roles
+                    .iter()
+                    .find(|(_, role)| matches!(role, ParamRole::Scope | ParamRole::Isolate))
+                    .map(|(name, _)| name),
+                method_attrs.promise,
+                method_attrs.fast_required,
+                &quote!(<#self_ty>::#fn_name),
+                &call_args,
+                &unwrap_fn,
+                receiver.mutability.is_some(),
+                &borrow_error,
+                int64,
+                &counters,
+            );
+            match fast_method {
+                Ok(fast_method) => fast_method.unwrap_or_default(),
+                Err(err) => return err.to_compile_error(),
+            }
+        } else {
+            Default::default()
+        };
+
+        wrappers.push(quote! {
+            /// V8 callback wrapper - auto-generated by glue_v8::methods
+            pub fn #wrapper_name(
+                __v8g_scope: &mut v8::PinScope,
+                __v8g_args: v8::FunctionCallbackArguments,
+                mut __v8g_rv: v8::ReturnValue,
+            ) {
+                #count_slow
+                let Some(__v8g_cell) = #unwrap_fn(__v8g_scope, __v8g_args.this()) else {
+                    let msg = v8::String::new(__v8g_scope, "Illegal invocation").unwrap();
+                    let err = v8::Exception::type_error(__v8g_scope, msg);
+                    __v8g_scope.throw_exception(err);
+                    return;
+                };
+
+                #(#arg_extractions)*
+
+                #borrow else {
+                    let msg = v8::String::new(__v8g_scope, #borrow_error).unwrap();
+                    let err = v8::Exception::error(__v8g_scope, msg);
+                    __v8g_scope.throw_exception(err);
+                    return;
+                };
+
+                #call_and_return
+            }
+
+            #fast_fns
+        });
+        wrappers.push(generate_raw_callback(
+            &wrapper_name,
+            &quote!(Self::#wrapper_name),
+        ));
+
+        methods.push(ClassMethod {
+            js_name,
+            wrapper_name,
+            cfunction_names,
+        });
+    }
+
+    // Without a #[constructor], `new Class()` throws (instances are created from Rust)
+    let constructor = constructor.unwrap_or_else(|| {
+        let wrapper_name = syn::Ident::new("__v8g_illegal_constructor", type_ident.span());
+
+        wrappers.push(quote! {
+            /// V8 constructor callback throwing for classes without #[constructor] - auto-generated by glue_v8::methods
+            #[doc(hidden)]
+            pub fn #wrapper_name(
+                scope: &mut v8::PinScope,
+                _args: v8::FunctionCallbackArguments,
+                _rv: v8::ReturnValue,
+            ) {
+                let msg = v8::String::new(scope, "Illegal constructor").unwrap();
+                let err = v8::Exception::type_error(scope, msg);
+                scope.throw_exception(err);
+            }
+        });
+        wrappers.push(generate_raw_callback(
+            &wrapper_name,
+            &quote!(Self::#wrapper_name),
+        ));
+        wrapper_name
+    });
+    let constructor_raw = raw_callback_name(&constructor);
+
+    let method_registrations = methods.iter().map(|m| {
+        let js_name = &m.js_name;
+        let raw_name = raw_callback_name(&m.wrapper_name);
+        let cfunction_names = &m.cfunction_names;
+        let build = if cfunction_names.is_empty() {
+            quote!(build(scope))
+        } else {
+            quote!(build_fast(scope, &[#(<#self_ty>::#cfunction_names),*]))
+        };
+
+        quote! {
+            let method = v8::FunctionTemplate::builder_raw(<#self_ty>::#raw_name)
+                .signature(signature)
+                .constructor_behavior(v8::ConstructorBehavior::Throw)
+                .#build;
+            let key = v8::String::new(scope, #js_name).unwrap();
+            prototype.set(key.into(), method.into());
+        }
+    });
+
+    // Snapshot references: constructor and method callbacks, and the fast functions
+    let method_refs = methods.iter().map(|m| {
+        let raw_name = raw_callback_name(&m.wrapper_name);
+        let cfunctions: Vec<_> = m
+            .cfunction_names
+            .iter()
+            .map(|cf| quote!(<#self_ty>::#cf))
+            .collect();
+        let fast_refs = fast_external_refs(&cfunctions);
+
+        quote! {
+            v8::ExternalReference { function: <#self_ty>::#raw_name },
+            #fast_refs
+        }
+    });
+
+    let internal_field_count = INTERNAL_FIELD_COUNT;
+
+    quote! {
+        #item
+
+        impl #self_ty {
+            #(#wrappers)*
+        }
+
+        #(#counter_statics)*
+
+        /// External references of the class, for V8 snapshots - auto-generated by glue_v8::methods
+        pub const #external_refs_const: &[v8::ExternalReference] = &[
+            v8::ExternalReference { function: <#self_ty>::#constructor_raw },
+            #(#method_refs)*
+        ];
+
+        /// Get the class FunctionTemplate, creating it on first use in this isolate.
+        ///
+        /// # Example
+        ///
+        /// ```ignore
+        /// let class = MyType_v8_class_template(scope).get_function(scope).unwrap();
+        /// ```
+        #[allow(non_snake_case)]
+        pub fn #template_fn<'s>(
+            scope: &mut v8::PinScope<'s, '_>,
+        ) -> v8::Local<'s, v8::FunctionTemplate> {
+            // Isolate slot caching the template, so every context shares one class
+            struct ClassTemplate(v8::Global<v8::FunctionTemplate>);
+
+            if let Some(cached) = scope.get_slot::<ClassTemplate>() {
+                return v8::Local::new(scope, &cached.0);
+            }
+
+            let template = v8::FunctionTemplate::builder_raw(<#self_ty>::#constructor_raw).build(scope);
+            let class_name = v8::String::new(scope, #class_name_const).unwrap();
+            template.set_class_name(class_name);
+            template
+                .instance_template(scope)
+                .set_internal_field_count(#internal_field_count);
+
+            let prototype = template.prototype_template(scope);
+            let signature = v8::Signature::new(scope, template);
+            #(#method_registrations)*
+
+            let global = v8::Global::new(scope, template);
+            scope.set_slot(ClassTemplate(global));
+            template
+        }
+
+        /// Create a JS object wrapping a Rust value, without calling the JS constructor.
+        #[allow(non_snake_case, private_interfaces)]
+        pub fn #new_instance_fn<'s>(
+            scope: &mut v8::PinScope<'s, '_>,
+            value: #self_ty,
+        ) -> v8::Local<'s, v8::Object> {
+            let template = #template_fn(scope);
+            let object = template
+                .instance_template(scope)
+                .new_instance(scope)
+                .unwrap();
+            let wrapped = #wrap_fn(scope, object, value);
+            debug_assert!(wrapped, "instances of the class template have its internal fields");
+            object
+        }
+    }
+}
@@ -1,152 +1,587 @@
 //! Code generation for V8 callback wrappers.
+//!
+//! Inside the wrappers, every binding besides the user parameters is named
+//! `__v8g_*` (`__v8g_scope`, `__v8g_args`, `__v8g_rv`, `__v8g_state`, ...), so a
+//! parameter may take any name without shadowing them.
 
 use quote::quote;
-use syn::Type;
+use syn::{ReturnType, Type};
 
+use crate::parse::{Int64Repr, OpParams, ParamRole, StateMode};
+use crate::snapshot::raw_callback_name;
 use crate::types::{
-    get_option_inner_type, get_rc_inner_type, get_v8_local_inner_type, v8_local_extraction,
+    PrimitiveType, StateKind, get_int64_signedness, get_option_inner_type, get_primitive_type,
+    get_registry_state_type, get_result_ok_type, get_v8_local_inner_type, is_refcell_type,
+    is_str_ref_type, v8_local_extraction,
 };
 
+/// Largest integer a Number holds exactly (`Number.MAX_SAFE_INTEGER`)
+pub const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+/// Error message of a returned `i64`/`u64` too large for a Number
+pub const UNSAFE_RETURN_MESSAGE: &str = "return value is not a safe integer";
+
 /// Generate state extraction code for the slow path.
 ///
-/// State is extracted from FunctionTemplate data (External containing Rc<T>).
+/// State is extracted from FunctionTemplate data (External pointing to the inner `T`).
 /// This is the unified approach - same pattern for both fast and non-fast functions.
+/// `Rc`/`Arc` state is cloned for the duration of the call, references are
+/// borrowed from the data (see `StateKind`).
+///
+/// In the slot modes, the template data stays empty and the state is resolved
+/// from an isolate slot (see `generate_slot_state_extraction`) or a context slot
+/// (see `generate_context_state_extraction`) instead.
+///
+/// With `state_mut` (a `state: &mut T` parameter), the `RefCell<T>` state is then
+/// mutably borrowed for the call (see `generate_state_borrow_mut`).
+///
+/// A missing or unsupported state type is reported at the state parameter.
 pub fn generate_state_extraction(
-    has_state: bool,
-    state_type: &Option<Type>,
-) -> proc_macro2::TokenStream {
-    if !has_state {
-        return quote! {};
+    op: &OpParams,
+    state_type: Option<&Type>,
+    state_mode: StateMode,
+) -> syn::Result<proc_macro2::TokenStream> {
+    let Some(state_param) = op.state_param.as_deref().filter(|_| op.has_state) else {
+        return Ok(quote! {});
+    };
+
+    let Some(state_ty) = state_type else {
+        return Err(syn::Error::new_spanned(
+            state_param,
+            "the state parameter needs a state type: #[glue_v8::method(state = YourStateType)]",
+        ));
+    };
+
+    if op.state_mut && !is_refcell_type(StateKind::of(state_ty).inner()) {
+        return Err(syn::Error::new_spanned(
+            state_param,
+            "`&mut` state needs a `RefCell` state type, e.g. `state = Rc<RefCell<T>>`",
+        ));
     }
 
-    if let Some(state_ty) = state_type {
-        let state_ty_str = quote!(#state_ty).to_string();
+    let extraction = match state_mode {
+        StateMode::IsolateSlot => generate_slot_state_extraction(state_ty)?,
+        StateMode::ContextSlot => generate_context_state_extraction(state_ty)?,
+        StateMode::Borrowed | StateMode::Owned => generate_data_state_extraction(state_ty),
+    };
+    let borrow = op.state_mut.then(|| generate_state_borrow_mut(false));
 
-        // Determine the inner type (unwrap Rc if present)
-        let inner_ty = if let Some(inner) = get_rc_inner_type(state_ty) {
-            inner.clone()
-        } else {
-            state_ty.clone()
+    Ok(quote! {
+        #extraction
+        #borrow
+    })
+}
+
+/// Generate state extraction from the function data (External pointing to the inner state)
+fn generate_data_state_extraction(state_ty: &Type) -> proc_macro2::TokenStream {
+    let state_ty_str = quote!(#state_ty).to_string();
+    let kind = StateKind::of(state_ty);
+    let inner_ty = kind.inner();
+    let (binding_ty, state) = match kind {
+        StateKind::Rc(_) => (
+            quote!(std::rc::Rc<#inner_ty>),
+            quote!(std::rc::Rc::clone(&*std::mem::ManuallyDrop::new(
+                std::rc::Rc::from_raw(ptr)
+            ))),
+        ),
+        StateKind::Arc(_) => (
+            quote!(std::sync::Arc<#inner_ty>),
+            quote!(std::sync::Arc::clone(&*std::mem::ManuallyDrop::new(
+                std::sync::Arc::from_raw(ptr)
+            ))),
+        ),
+        StateKind::Static(_) => (quote!(&'static #inner_ty), quote!(&*ptr)),
+        StateKind::Value(_) => (quote!(&#inner_ty), quote!(&*ptr)),
+    };
+
+    quote! {
+        let __v8g_state: #binding_ty = unsafe {
+            let data = __v8g_args.data();
+
+            if data.is_undefined() || data.is_null() {
+                let msg = v8::String::new(__v8g_scope, concat!("internal error: state data not set for ", #state_ty_str)).unwrap();
+                let err = v8::Exception::error(__v8g_scope, msg);
+                __v8g_scope.throw_exception(err);
+                return;
+            }
+
+            let external = v8::Local::<v8::External>::try_from(data).unwrap();
+            let ptr = external.value() as *const #inner_ty;
+            #state
         };
+    }
+}
 
-        // Extract state from function data (External containing Rc<State>)
+/// Generate the mutable borrow of `RefCell` state, rebinding `__v8g_state` to the `RefMut`.
+///
+/// The borrow fails when JS re-enters an op of the same state while it is
+/// borrowed (e.g. from a callback invoked by the op); this throws an `Error`
+/// instead of panicking. In the fast path, the exception is thrown through a
+/// `CallbackScope` created from the `__v8g_options` of the fast call.
+pub fn generate_state_borrow_mut(fast: bool) -> proc_macro2::TokenStream {
+    let message = "op re-entered while state is borrowed";
+    let throw = if fast {
         quote! {
-            let state: #state_ty = unsafe {
-                let data = args.data();
-
-                if data.is_undefined() || data.is_null() {
-                    let msg = v8::String::new(scope, concat!("internal error: state data not set for ", #state_ty_str)).unwrap();
-                    let err = v8::Exception::error(scope, msg);
-                    scope.throw_exception(err);
-                    return;
-                }
-
-                let external = v8::Local::<v8::External>::try_from(data).unwrap();
-                let ptr = external.value() as *const #inner_ty;
-                std::rc::Rc::clone(&*std::mem::ManuallyDrop::new(std::rc::Rc::from_raw(ptr)))
-            };
+            let __v8g_scope = std::pin::pin!(unsafe { v8::CallbackScope::new(&*__v8g_options) });
+            let __v8g_scope = __v8g_scope.init();
+            let msg = v8::String::new(&__v8g_scope, #message).unwrap();
+            let err = v8::Exception::error(&__v8g_scope, msg);
+            __v8g_scope.throw_exception(err);
+            return Default::default();
         }
     } else {
         quote! {
-            compile_error!("Function has 'state' parameter but no state type specified. Use #[glue_v8::method(state = YourStateType)]");
+            let msg = v8::String::new(__v8g_scope, #message).unwrap();
+            let err = v8::Exception::error(__v8g_scope, msg);
+            __v8g_scope.throw_exception(err);
+            return;
         }
+    };
+
+    quote! {
+        let mut __v8g_state = match __v8g_state.try_borrow_mut() {
+            Ok(state) => state,
+            Err(_) => {
+                #throw
+            }
+        };
     }
 }
 
+/// Argument passing the extracted `__v8g_state` to the op.
+///
+/// `Rc`/`Arc` state is passed by reference (by value to async ops, whose future
+/// must be 'static); `&'static T` and plain `T` state is already a `&T`.
+/// Mutably borrowed state (`state_mut`) is passed as `&mut T`.
+pub fn state_call_arg(
+    state_type: Option<&Type>,
+    is_async: bool,
+    state_mut: bool,
+) -> proc_macro2::TokenStream {
+    let is_reference = state_type.is_some_and(|ty| StateKind::of(ty).is_reference());
+
+    if state_mut {
+        quote! { &mut __v8g_state }
+    } else if is_async || is_reference {
+        quote! { __v8g_state }
+    } else {
+        quote! { &__v8g_state }
+    }
+}
+
+/// Generate the arguments of the user function call, in declaration order.
+///
+/// The scope is passed for both the scope and isolate roles (`&mut PinScope`
+/// derefs to the isolate). `state_arg` passes the state (see `state_call_arg`)
+/// and `registry_args` the `#[state]` params, in `op.states` order.
+pub fn generate_call_args(
+    op: &OpParams,
+    state_arg: &proc_macro2::TokenStream,
+    registry_args: &[proc_macro2::TokenStream],
+) -> Vec<proc_macro2::TokenStream> {
+    let mut registry_args = registry_args.iter();
+
+    op.roles
+        .iter()
+        .map(|(name, role)| match role {
+            ParamRole::Scope | ParamRole::Isolate => quote! { __v8g_scope },
+            ParamRole::State => state_arg.clone(),
+            ParamRole::RegistryState => registry_args.next().cloned().unwrap_or_default(),
+            ParamRole::Arg => quote! { #name },
+        })
+        .collect()
+}
+
 /// Generate argument extraction code for the slow path.
 ///
-/// Handles various types:
-/// - Option<T>: None if undefined/null
-/// - v8::Local<T>: Direct V8 type extraction
-/// - Other types: serde_v8 deserialization
+/// Each parameter is converted from `__v8g_args.get(i)`, see `generate_value_extraction`.
 pub fn generate_arg_extractions(
     params: &[(syn::Ident, Box<Type>)],
+    int64: Int64Repr,
 ) -> Vec<proc_macro2::TokenStream> {
     params
         .iter()
         .enumerate()
         .map(|(i, (name, ty))| {
             let idx = i as i32;
+            let label = arg_label(i);
 
-            // Check if this is an Option<T> type
-            if let Some(inner_ty) = get_option_inner_type(ty) {
-                // Optional parameter: None if undefined/null, Some(value) otherwise
-                let inner_type_str = quote!(#inner_ty).to_string();
-                let error_prefix = format!("argument {}: expected {}", idx, inner_type_str);
+            generate_value_extraction(name, ty, &quote!(__v8g_args.get(#idx)), &label, int64)
+        })
+        .collect()
+}
 
-                quote! {
-                    let #name: #ty = {
-                        let __v8g_arg = args.get(#idx);
-                        if __v8g_arg.is_undefined() || __v8g_arg.is_null() {
-                            None
-                        } else {
-                            match serde_v8::from_v8_any(scope, __v8g_arg) {
-                                Ok(v) => Some(v),
-                                Err(e) => {
-                                    let msg = v8::String::new(scope, &format!("{}: {}", #error_prefix, e)).unwrap();
-                                    let err = v8::Exception::type_error(scope, msg);
-                                    scope.throw_exception(err);
-                                    return;
-                                }
-                            }
-                        }
-                    };
-                }
-            } else if let Some(inner_type) = get_v8_local_inner_type(ty) {
-                // V8 Local type - generate direct extraction
-                match inner_type.as_str() {
-                    "Function" => v8_local_extraction(name, idx, "Function", "is_function"),
-                    "Object" => v8_local_extraction(name, idx, "Object", "is_object"),
-                    "Array" => v8_local_extraction(name, idx, "Array", "is_array"),
-                    "Uint8Array" => v8_local_extraction(name, idx, "Uint8Array", "is_uint8_array"),
-                    "ArrayBuffer" => {
-                        v8_local_extraction(name, idx, "ArrayBuffer", "is_array_buffer")
-                    }
-                    "String" => v8_local_extraction(name, idx, "String", "is_string"),
-                    "Number" => v8_local_extraction(name, idx, "Number", "is_number"),
-                    "Value" => {
-                        // No type check needed for Value
-                        quote! {
-                            let #name: v8::Local<v8::Value> = args.get(#idx);
-                        }
-                    }
-                    _ => {
-                        // For other V8 types, try generic conversion
-                        let type_str = quote!(#ty).to_string();
-                        let error_msg = format!("argument {}: expected {}", idx, type_str);
-
-                        quote! {
-                            let #name: #ty = match args.get(#idx).try_into() {
-                                Ok(v) => v,
-                                Err(_) => {
-                                    let msg = v8::String::new(scope, #error_msg).unwrap();
-                                    let err = v8::Exception::type_error(scope, msg);
-                                    scope.throw_exception(err);
-                                    return;
-                                }
-                            };
-                        }
-                    }
+/// Label of the `i`-th JS argument in error messages
+pub fn arg_label(i: usize) -> String {
+    format!("argument {}", i)
+}
+
+/// Message of the RangeError thrown for an `i64`/`u64` argument a Number can't hold exactly
+pub fn unsafe_arg_message(label: &str, signed: bool) -> String {
+    if signed {
+        format!("{}: expected a safe integer", label)
+    } else {
+        format!("{}: expected a non-negative safe integer", label)
+    }
+}
+
+/// Generate an expression converting `source` to an `i64`/`u64` (`signed`) per
+/// `int64`; other JS types throw a TypeError, out of range values a RangeError.
+///
+/// Without `int64`, serde_v8 converts it as it does for other types.
+fn generate_int64_extraction(
+    source: &proc_macro2::TokenStream,
+    label: &str,
+    signed: bool,
+    int64: Int64Repr,
+) -> proc_macro2::TokenStream {
+    let throw = |exception: proc_macro2::TokenStream, msg: String| {
+        quote! {
+            let msg = v8::String::new(__v8g_scope, #msg).unwrap();
+            let err = v8::Exception::#exception(__v8g_scope, msg);
+            __v8g_scope.throw_exception(err);
+            return;
+        }
+    };
+
+    match int64 {
+        Int64Repr::Serde => {
+            let ty: Type = if signed {
+                syn::parse_quote!(i64)
+            } else {
+                syn::parse_quote!(u64)
+            };
+            generate_serde_extraction(&ty, source, label)
+        }
+        Int64Repr::Number => {
+            let int_ty = if signed { quote!(i64) } else { quote!(u64) };
+            let max = MAX_SAFE_INTEGER as f64;
+            let min = if signed { -max } else { 0.0 };
+            let not_number = throw(quote!(type_error), format!("{}: expected a number", label));
+            let out_of_range = throw(quote!(range_error), unsafe_arg_message(label, signed));
+
+            quote! {{
+                let __v8g_num = match v8::Local::<v8::Number>::try_from(#source) {
+                    Ok(num) => num.value(),
+                    Err(_) => { #not_number }
+                };
+                if __v8g_num.fract() != 0.0 || !(#min..=#max).contains(&__v8g_num) {
+                    #out_of_range
                 }
+                __v8g_num as #int_ty
+            }}
+        }
+        Int64Repr::BigInt => {
+            let (value_fn, int_name) = if signed {
+                (quote!(i64_value), "i64")
             } else {
-                // Use serde_v8 for regular types
+                (quote!(u64_value), "u64")
+            };
+            let not_bigint = throw(quote!(type_error), format!("{}: expected a BigInt", label));
+            let out_of_range = throw(
+                quote!(range_error),
+                format!("{}: BigInt out of range for {}", label, int_name),
+            );
+
+            quote! {{
+                let (__v8g_int, __v8g_lossless) = match v8::Local::<v8::BigInt>::try_from(#source) {
+                    Ok(big) => big.#value_fn(),
+                    Err(_) => { #not_bigint }
+                };
+                if !__v8g_lossless {
+                    #out_of_range
+                }
+                __v8g_int
+            }}
+        }
+    }
+}
+
+/// Generate an expression converting `source` to a primitive type without
+/// serde_v8: numbers from a Number (`i32`/`u32` wrap like JS `ToInt32` /
+/// `ToUint32`), `String` from a string, and `bool` from any value by its
+/// truthiness (`ToBoolean`).
+///
+/// Other JS types (a BigInt for a number, a String object) go through serde_v8,
+/// which converts them or throws its TypeError.
+fn generate_primitive_extraction(
+    ty: &Type,
+    primitive: PrimitiveType,
+    source: &proc_macro2::TokenStream,
+    label: &str,
+) -> proc_macro2::TokenStream {
+    let fallback = generate_serde_extraction(ty, &quote!(__v8g_value), label);
+    let (local_ty, convert) = match primitive {
+        PrimitiveType::Bool => return quote!(#source.boolean_value(__v8g_scope)),
+        // ToInt32/ToUint32 of a Number can't throw
+        PrimitiveType::I32 => (
+            quote!(v8::Number),
+            quote!(converted.int32_value(__v8g_scope).unwrap_or_default()),
+        ),
+        PrimitiveType::U32 => (
+            quote!(v8::Number),
+            quote!(converted.uint32_value(__v8g_scope).unwrap_or_default()),
+        ),
+        PrimitiveType::F32 => (quote!(v8::Number), quote!(converted.value() as f32)),
+        PrimitiveType::F64 => (quote!(v8::Number), quote!(converted.value())),
+        PrimitiveType::String => (
+            quote!(v8::String),
+            quote!(converted.to_rust_string_lossy(__v8g_scope)),
+        ),
+    };
+
+    quote! {{
+        let __v8g_value = #source;
+        match v8::Local::<#local_ty>::try_from(__v8g_value) {
+            Ok(converted) => #convert,
+            Err(_) => #fallback,
+        }
+    }}
+}
+
+/// Generate an expression converting `source` to `ty` via serde_v8, throwing a
+/// TypeError on failure.
+fn generate_serde_extraction(
+    ty: &Type,
+    source: &proc_macro2::TokenStream,
+    label: &str,
+) -> proc_macro2::TokenStream {
+    let type_str = quote!(#ty).to_string();
+    let error_prefix = format!("{}: expected {}", label, type_str);
+
+    quote! {
+        match serde_v8::from_v8_any(__v8g_scope, #source) {
+            Ok(v) => v,
+            Err(e) => {
+                let msg = v8::String::new(__v8g_scope, &format!("{}: {}", #error_prefix, e)).unwrap();
+                let err = v8::Exception::type_error(__v8g_scope, msg);
+                __v8g_scope.throw_exception(err);
+                return;
+            }
+        }
+    }
+}
+
+/// Generate an expression converting `source` to `ty`: directly for `i64`/`u64`
+/// (per `int64`) and primitive types, via serde_v8 otherwise.
+fn generate_conversion(
+    ty: &Type,
+    source: &proc_macro2::TokenStream,
+    label: &str,
+    int64: Int64Repr,
+) -> proc_macro2::TokenStream {
+    if let Some(signed) = get_int64_signedness(ty) {
+        generate_int64_extraction(source, label, signed, int64)
+    } else if let Some(primitive) = get_primitive_type(ty) {
+        generate_primitive_extraction(ty, primitive, source, label)
+    } else {
+        generate_serde_extraction(ty, source, label)
+    }
+}
+
+/// Generate extraction code binding `name: ty` from a `v8::Local<v8::Value>` expression.
+///
+/// `label` prefixes error messages (e.g. "argument 0", "value").
+///
+/// Handles various types:
+/// - Option<T>: None if undefined/null
+/// - v8::Local<T>: Direct V8 type extraction
+/// - i64/u64: a Number or BigInt, per `int64` (via serde_v8 without it)
+/// - bool, i32, u32, f32, f64, String (and `&str`): direct conversion
+/// - Other types: serde_v8 deserialization
+pub fn generate_value_extraction(
+    name: &syn::Ident,
+    ty: &Type,
+    source: &proc_macro2::TokenStream,
+    label: &str,
+    int64: Int64Repr,
+) -> proc_macro2::TokenStream {
+    if let Some(inner_ty) = get_option_inner_type(ty) {
+        // Optional parameter: None if undefined/null, Some(value) otherwise
+        let conversion = generate_conversion(inner_ty, &quote!(__v8g_arg), label, int64);
+
+        quote! {
+            let #name: #ty = {
+                let __v8g_arg = #source;
+                if __v8g_arg.is_undefined() || __v8g_arg.is_null() {
+                    None
+                } else {
+                    Some(#conversion)
+                }
+            };
+        }
+    } else if let Some(inner_type) = get_v8_local_inner_type(ty) {
+        // V8 Local type - generate direct extraction
+        match inner_type.as_str() {
+            "Function" => v8_local_extraction(name, source, label, "Function", "is_function"),
+            "Object" => v8_local_extraction(name, source, label, "Object", "is_object"),
+            "Array" => v8_local_extraction(name, source, label, "Array", "is_array"),
+            "Uint8Array" => {
+                v8_local_extraction(name, source, label, "Uint8Array", "is_uint8_array")
+            }
+            "ArrayBuffer" => {
+                v8_local_extraction(name, source, label, "ArrayBuffer", "is_array_buffer")
+            }
+            "String" => v8_local_extraction(name, source, label, "String", "is_string"),
+            "Number" => v8_local_extraction(name, source, label, "Number", "is_number"),
+            "Value" => {
+                // No type check needed for Value
+                quote! {
+                    let #name: v8::Local<v8::Value> = #source;
+                }
+            }
+            _ => {
+                // For other V8 types, try generic conversion
                 let type_str = quote!(#ty).to_string();
-                let error_prefix = format!("argument {}: expected {}", idx, type_str);
+                let error_msg = format!("{}: expected {}", label, type_str);
 
                 quote! {
-                    let #name: #ty = match serde_v8::from_v8_any(scope, args.get(#idx)) {
+                    let #name: #ty = match #source.try_into() {
                         Ok(v) => v,
-                        Err(e) => {
-                            let msg = v8::String::new(scope, &format!("{}: {}", #error_prefix, e)).unwrap();
-                            let err = v8::Exception::type_error(scope, msg);
-                            scope.throw_exception(err);
+                        Err(_) => {
+                            let msg = v8::String::new(__v8g_scope, #error_msg).unwrap();
+                            let err = v8::Exception::type_error(__v8g_scope, msg);
+                            __v8g_scope.throw_exception(err);
                             return;
                         }
                     };
                 }
             }
-        })
-        .collect()
+        }
+    } else if is_str_ref_type(ty) {
+        // `&str` borrows from a converted `String`
+        let owned =
+            generate_value_extraction(name, &syn::parse_quote!(String), source, label, int64);
+
+        quote! {
+            #owned
+            let #name: &str = &#name;
+        }
+    } else {
+        let conversion = generate_conversion(ty, source, label, int64);
+
+        quote! {
+            let #name: #ty = #conversion;
+        }
+    }
+}
+
+/// How an op's return value (the `Ok` value of a `Result`) converts to JS
+#[derive(Clone, Copy, PartialEq)]
+pub enum ReturnConversion {
+    /// serde_v8 (also `i64`/`u64` without `int64`)
+    Serde,
+    /// `i64`/`u64` (`signed`), per its `int64` representation
+    Int64 { repr: Int64Repr, signed: bool },
+    /// Direct conversion of a primitive type
+    Primitive(PrimitiveType),
+}
+
+/// How an op converts its return value to JS
+pub fn return_conversion(output: &ReturnType, repr: Int64Repr) -> ReturnConversion {
+    let ReturnType::Type(_, ty) = output else {
+        return ReturnConversion::Serde;
+    };
+    let ty = get_result_ok_type(ty).unwrap_or(ty);
+
+    if let Some(signed) = get_int64_signedness(ty).filter(|_| repr != Int64Repr::Serde) {
+        ReturnConversion::Int64 { repr, signed }
+    } else if let Some(primitive) = get_primitive_type(ty) {
+        ReturnConversion::Primitive(primitive)
+    } else {
+        ReturnConversion::Serde
+    }
+}
+
+/// Generate statements converting a returned `value` to a JS value bound to
+/// `v8_value`, then running `then`.
+///
+/// `i64`/`u64` values follow their `int64` representation: a value a Number
+/// can't hold exactly runs `on_error` with a RangeError bound to `error`.
+/// Primitive values are converted directly, other values via serde_v8. A value
+/// that can't be converted (a serde_v8 error, a string too long for V8) runs
+/// `on_error` with a TypeError naming `op_name`.
+fn generate_return_to_v8(
+    value: &proc_macro2::TokenStream,
+    conversion: ReturnConversion,
+    op_name: &str,
+    then: &proc_macro2::TokenStream,
+    on_error: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let error_prefix = format!("{}: failed to convert the return value", op_name);
+
+    match conversion {
+        ReturnConversion::Serde
+        | ReturnConversion::Int64 {
+            repr: Int64Repr::Serde,
+            ..
+        } => quote! {
+            match serde_v8::to_v8(__v8g_scope, #value) {
+                Ok(v8_value) => {
+                    #then
+                }
+                Err(e) => {
+                    let msg = v8::String::new(__v8g_scope, &format!("{}: {}", #error_prefix, e)).unwrap();
+                    let error = v8::Exception::type_error(__v8g_scope, msg);
+                    #on_error
+                }
+            }
+        },
+        ReturnConversion::Primitive(primitive) => {
+            let new_value = match primitive {
+                PrimitiveType::String => {
+                    let too_long = format!("{}: string too long", error_prefix);
+                    return quote! {
+                        if let Some(v8_value) = v8::String::new(__v8g_scope, &#value) {
+                            let v8_value: v8::Local<v8::Value> = v8_value.into();
+                            #then
+                        } else {
+                            let msg = v8::String::new(__v8g_scope, #too_long).unwrap();
+                            let error = v8::Exception::type_error(__v8g_scope, msg);
+                            #on_error
+                        }
+                    };
+                }
+                PrimitiveType::Bool => quote!(v8::Boolean::new(__v8g_scope, #value)),
+                PrimitiveType::I32 => quote!(v8::Integer::new(__v8g_scope, #value)),
+                PrimitiveType::U32 => quote!(v8::Integer::new_from_unsigned(__v8g_scope, #value)),
+                PrimitiveType::F32 => quote!(v8::Number::new(__v8g_scope, f64::from(#value))),
+                PrimitiveType::F64 => quote!(v8::Number::new(__v8g_scope, #value)),
+            };
+            quote! {
+                let v8_value: v8::Local<v8::Value> = #new_value.into();
+                #then
+            }
+        }
+        ReturnConversion::Int64 {
+            repr: Int64Repr::Number,
+            ..
+        } => quote! {
+            let __v8g_int = #value;
+            if (__v8g_int as i128).unsigned_abs() <= #MAX_SAFE_INTEGER as u128 {
+                let v8_value: v8::Local<v8::Value> = v8::Number::new(__v8g_scope, __v8g_int as f64).into();
+                #then
+            } else {
+                let msg = v8::String::new(__v8g_scope, #UNSAFE_RETURN_MESSAGE).unwrap();
+                let error = v8::Exception::range_error(__v8g_scope, msg);
+                #on_error
+            }
+        },
+        ReturnConversion::Int64 {
+            repr: Int64Repr::BigInt,
+            signed,
+        } => {
+            let new_fn = if signed {
+                quote!(new_from_i64)
+            } else {
+                quote!(new_from_u64)
+            };
+            quote! {
+                let v8_value: v8::Local<v8::Value> = v8::BigInt::#new_fn(__v8g_scope, #value).into();
+                #then
+            }
+        }
+    }
 }
 
 /// Generate the function call and return value handling code.
@@ -154,111 +589,522 @@ pub fn generate_arg_extractions(
 /// Handles:
 /// - Promise mode: wrap in Promise, resolve/reject
 /// - Result<T, E>: throw on Err, return Ok value
-/// - Regular return: convert via serde_v8
+/// - Regular return: convert per `conversion` (see `return_conversion`),
+///   throwing (or rejecting) if that fails
 /// - No return: just call
+///
+/// `op_name` names the op in conversion errors.
 pub fn generate_call_and_return(
-    fn_name: &syn::Ident,
+    callee: &proc_macro2::TokenStream,
     call_args: &[proc_macro2::TokenStream],
     has_return: bool,
     returns_result: bool,
     is_promise: bool,
+    conversion: ReturnConversion,
+    op_name: &str,
 ) -> proc_macro2::TokenStream {
-    if is_promise {
-        // Promise mode: wrap in a Promise, handle Result<T, E> if applicable
-        if returns_result {
-            quote! {
-                let resolver = v8::PromiseResolver::new(scope).unwrap();
-                let promise = resolver.get_promise(scope);
-                rv.set(promise.into());
-
-                match #fn_name(#(#call_args),*) {
-                    Ok(value) => {
-                        if let Ok(v8_value) = serde_v8::to_v8(scope, value) {
-                            resolver.resolve(scope, v8_value);
-                        }
-                    }
-                    Err(err) => {
-                        let err_str = format!("{}", err);
-                        let msg = v8::String::new(scope, &err_str).unwrap();
-                        let error = v8::Exception::error(scope, msg);
-                        resolver.reject(scope, error);
-                    }
-                }
-            }
-        } else if has_return {
-            // Promise mode but not Result - just resolve with value
-            quote! {
-                let resolver = v8::PromiseResolver::new(scope).unwrap();
-                let promise = resolver.get_promise(scope);
-                rv.set(promise.into());
+    // Numbers and booleans set the return value without allocating a handle
+    let set_rv = match conversion {
+        ReturnConversion::Primitive(PrimitiveType::Bool) => quote!(__v8g_rv.set_bool(value);),
+        ReturnConversion::Primitive(PrimitiveType::I32) => quote!(__v8g_rv.set_int32(value);),
+        ReturnConversion::Primitive(PrimitiveType::U32) => quote!(__v8g_rv.set_uint32(value);),
+        ReturnConversion::Primitive(PrimitiveType::F32) => {
+            quote!(__v8g_rv.set_double(f64::from(value));)
+        }
+        ReturnConversion::Primitive(PrimitiveType::F64) => quote!(__v8g_rv.set_double(value);),
+        _ => generate_return_to_v8(
+            &quote!(value),
+            conversion,
+            op_name,
+            &quote!(__v8g_rv.set(v8_value);),
+            &quote!(__v8g_scope.throw_exception(error);),
+        ),
+    };
 
-                let result = #fn_name(#(#call_args),*);
-                if let Ok(v8_value) = serde_v8::to_v8(scope, result) {
-                    resolver.resolve(scope, v8_value);
-                }
-            }
+    if is_promise {
+        // Promise mode: wrap in a Promise, settle it with the call result
+        let call = if has_return {
+            quote! { let __v8g_result = #callee(#(#call_args),*); }
         } else {
-            // Promise mode, no return - resolve with undefined
-            quote! {
-                let resolver = v8::PromiseResolver::new(scope).unwrap();
-                let promise = resolver.get_promise(scope);
-                rv.set(promise.into());
+            quote! { #callee(#(#call_args),*); }
+        };
+        let settlement = generate_promise_settlement(
+            &quote!(__v8g_result),
+            has_return,
+            returns_result,
+            conversion,
+            op_name,
+        );
 
-                #fn_name(#(#call_args),*);
-                resolver.resolve(scope, v8::undefined(scope).into());
-            }
+        quote! {
+            let __v8g_resolver = v8::PromiseResolver::new(__v8g_scope).unwrap();
+            let __v8g_promise = __v8g_resolver.get_promise(__v8g_scope);
+            __v8g_rv.set(__v8g_promise.into());
+
+            #call
+            #settlement
         }
     } else if returns_result {
         // Not promise mode but returns Result - throw on Err
         quote! {
-            match #fn_name(#(#call_args),*) {
+            match #callee(#(#call_args),*) {
                 Ok(value) => {
-                    if let Ok(v8_value) = serde_v8::to_v8(scope, value) {
-                        rv.set(v8_value);
-                    }
+                    #set_rv
                 }
                 Err(err) => {
                     let err_str = format!("{}", err);
-                    let msg = v8::String::new(scope, &err_str).unwrap();
-                    let error = v8::Exception::error(scope, msg);
-                    scope.throw_exception(error);
+                    let msg = v8::String::new(__v8g_scope, &err_str).unwrap();
+                    let error = v8::Exception::error(__v8g_scope, msg);
+                    __v8g_scope.throw_exception(error);
                 }
             }
         }
     } else if has_return {
         quote! {
-            let result = #fn_name(#(#call_args),*);
-            if let Ok(v8_result) = serde_v8::to_v8(scope, result) {
-                rv.set(v8_result);
+            let value = #callee(#(#call_args),*);
+            #set_rv
+        }
+    } else {
+        quote! {
+            #callee(#(#call_args),*);
+        }
+    }
+}
+
+/// Generate the code settling `__v8g_resolver` (v8::Local<v8::PromiseResolver>) with a call result.
+///
+/// Handles:
+/// - Result<T, E>: resolve with Ok value, reject with Error on Err
+/// - Regular return: resolve with the value converted per `conversion` (see
+///   `return_conversion`), rejecting with the conversion error if that fails
+/// - No return: resolve with undefined
+pub fn generate_promise_settlement(
+    result: &proc_macro2::TokenStream,
+    has_return: bool,
+    returns_result: bool,
+    conversion: ReturnConversion,
+    op_name: &str,
+) -> proc_macro2::TokenStream {
+    let resolve = |value: proc_macro2::TokenStream| {
+        generate_return_to_v8(
+            &value,
+            conversion,
+            op_name,
+            &quote!(__v8g_resolver.resolve(__v8g_scope, v8_value);),
+            &quote!(__v8g_resolver.reject(__v8g_scope, error);),
+        )
+    };
+
+    if returns_result {
+        let resolve = resolve(quote!(value));
+        quote! {
+            match #result {
+                Ok(value) => {
+                    #resolve
+                }
+                Err(err) => {
+                    let err_str = format!("{}", err);
+                    let msg = v8::String::new(__v8g_scope, &err_str).unwrap();
+                    let error = v8::Exception::error(__v8g_scope, msg);
+                    __v8g_resolver.reject(__v8g_scope, error);
+                }
             }
         }
+    } else if has_return {
+        // Not Result - just resolve with value
+        resolve(result.clone())
     } else {
+        // No return - resolve with undefined
         quote! {
-            #fn_name(#(#call_args),*);
+            __v8g_resolver.resolve(__v8g_scope, v8::undefined(__v8g_scope).into());
         }
     }
 }
 
-/// Generate a template helper function for functions with state.
+/// Generate statements naming the FunctionTemplate bound to `template`.
 ///
-/// This creates a FunctionTemplate with state passed via External data.
-/// Used for both fast and non-fast functions with state.
-pub fn generate_state_template(
+/// The class name becomes `fn.name` of functions created from the template
+/// (and shows up in stack traces).
+pub fn generate_template_naming(js_name: &str) -> proc_macro2::TokenStream {
+    quote! {
+        let class_name = v8::String::new(scope, #js_name).unwrap();
+        template.set_class_name(class_name);
+    }
+}
+
+/// Isolate slot type of snapshot-safe state, and the expression resolving
+/// `state: &Slot` to the state passed to the op.
+///
+/// Plain `T` can't be borrowed from the isolate across the call, so it has no slot.
+pub fn state_slot(kind: StateKind) -> Option<(proc_macro2::TokenStream, proc_macro2::TokenStream)> {
+    match kind {
+        StateKind::Rc(inner) => Some((
+            quote!(std::rc::Rc<#inner>),
+            quote!(std::rc::Rc::clone(state)),
+        )),
+        StateKind::Arc(inner) => Some((
+            quote!(std::sync::Arc<#inner>),
+            quote!(std::sync::Arc::clone(state)),
+        )),
+        StateKind::Static(inner) => Some((quote!(&'static #inner), quote!(*state))),
+        StateKind::Value(_) => None,
+    }
+}
+
+/// Context slot key of state resolved per realm, and the expression resolving
+/// `state: Rc<Key>` to the state passed to the op.
+///
+/// Context slots hold an `Rc<Key>`: `Rc<T>` state is stored as is, `Arc<T>` and
+/// `&'static T` state are wrapped. Plain `T` has no slot, like isolate slots.
+pub fn context_state_slot(
+    kind: StateKind,
+) -> Option<(proc_macro2::TokenStream, proc_macro2::TokenStream)> {
+    match kind {
+        StateKind::Rc(inner) => Some((quote!(#inner), quote!(state))),
+        StateKind::Arc(inner) => Some((
+            quote!(std::sync::Arc<#inner>),
+            quote!(std::sync::Arc::clone(&state)),
+        )),
+        StateKind::Static(inner) => Some((quote!(&'static #inner), quote!(*state))),
+        StateKind::Value(_) => None,
+    }
+}
+
+/// Error thrown when slot state is used before its slot (`"an isolate"`, `"a context"`) is set
+pub fn state_not_bound_message(slot: &str, slot_ty: &proc_macro2::TokenStream) -> String {
+    format!(
+        "state not bound: set {} slot of type {}",
+        slot,
+        slot_ty
+            .to_string()
+            .replace(' ', "")
+            .replace("std::rc::", "")
+            .replace("std::sync::", "")
+            .replace("'static", "'static ")
+    )
+}
+
+/// Generate snapshot-safe state extraction: the isolate slot type (`Rc<T>`,
+/// `Arc<T>` or `&'static T`) is the key.
+///
+/// Nothing pointer-like is stored in the template, so the same code resolves the
+/// state in a fresh isolate and in one restored from a snapshot, once the state
+/// is bound with `isolate.set_slot(state)`.
+pub fn generate_slot_state_extraction(state_ty: &Type) -> syn::Result<proc_macro2::TokenStream> {
+    let Some((slot_ty, resolve)) = state_slot(StateKind::of(state_ty)) else {
+        return Err(syn::Error::new_spanned(
+            state_ty,
+            "snapshot state must be an `Rc<T>`, `Arc<T>` or `&'static T`",
+        ));
+    };
+    let not_bound = state_not_bound_message("an isolate", &slot_ty);
+
+    Ok(quote! {
+        let __v8g_state = match __v8g_scope.get_slot::<#slot_ty>() {
+            Some(state) => #resolve,
+            None => {
+                let msg = v8::String::new(__v8g_scope, #not_bound).unwrap();
+                let err = v8::Exception::error(__v8g_scope, msg);
+                __v8g_scope.throw_exception(err);
+                return;
+            }
+        };
+    })
+}
+
+/// Generate per-realm state extraction from a slot of the current context.
+///
+/// Like isolate slot state, nothing is stored in the template, so the op can be
+/// registered with a plain `v8::Function::new`; each context binds its own state
+/// with `context.set_slot(Rc<Key>)` (see `context_state_slot`).
+pub fn generate_context_state_extraction(state_ty: &Type) -> syn::Result<proc_macro2::TokenStream> {
+    let Some((key_ty, resolve)) = context_state_slot(StateKind::of(state_ty)) else {
+        return Err(syn::Error::new_spanned(
+            state_ty,
+            "context slot state must be an `Rc<T>`, `Arc<T>` or `&'static T`",
+        ));
+    };
+    let not_bound = state_not_bound_message("a context", &quote!(std::rc::Rc<#key_ty>));
+
+    Ok(quote! {
+        let __v8g_state = match __v8g_scope.get_current_context().get_slot::<#key_ty>() {
+            Some(state) => #resolve,
+            None => {
+                let msg = v8::String::new(__v8g_scope, #not_bound).unwrap();
+                let err = v8::Exception::error(__v8g_scope, msg);
+                __v8g_scope.throw_exception(err);
+                return;
+            }
+        };
+    })
+}
+
+/// Generate extraction code for `#[state]` parameters, and the matching call arguments.
+///
+/// The state registry is the typed slot map of the current context, then of the
+/// isolate: each state is registered as an `Rc<T>` with `context.set_slot(..)`
+/// (per realm) or `isolate.set_slot(..)` (shared by every context). No template
+/// data is involved; a missing type throws an `Error` naming it.
+pub fn generate_registry_state_extractions(
+    states: &[(syn::Ident, Box<Type>)],
+) -> syn::Result<(Vec<proc_macro2::TokenStream>, Vec<proc_macro2::TokenStream>)> {
+    let mut extractions = Vec::new();
+    let mut call_args = Vec::new();
+
+    for (name, ty) in states {
+        let Some((state_ty, by_value)) = get_registry_state_type(ty) else {
+            return Err(syn::Error::new_spanned(
+                ty,
+                "`#[state]` parameters must be `&T`, `&Rc<T>` or `Rc<T>`",
+            ));
+        };
+        let state_ty_str = quote!(#state_ty).to_string().replace(' ', "");
+        let missing = format!(
+            "state not registered: {} (set a context or isolate slot of type Rc<{}>)",
+            state_ty_str, state_ty_str
+        );
+
+        extractions.push(quote! {
+            let #name: std::rc::Rc<#state_ty> = {
+                let context = __v8g_scope.get_current_context();
+                match context.get_slot::<#state_ty>() {
+                    Some(state) => state,
+                    None => match __v8g_scope.get_slot::<std::rc::Rc<#state_ty>>() {
+                        Some(state) => std::rc::Rc::clone(state),
+                        None => {
+                            let msg = v8::String::new(__v8g_scope, #missing).unwrap();
+                            let err = v8::Exception::error(__v8g_scope, msg);
+                            __v8g_scope.throw_exception(err);
+                            return;
+                        }
+                    },
+                }
+            };
+        });
+        call_args.push(if by_value {
+            quote! { #name }
+        } else {
+            quote! { &#name }
+        });
+    }
+
+    Ok((extractions, call_args))
+}
+
+/// Generate a `{fn}_v8_template(scope)` helper for functions without template data.
+pub fn generate_plain_template_fn(
     wrapper_name: &syn::Ident,
     template_fn_name: &syn::Ident,
-    state_type: &Type,
+    js_name: &str,
+    length: usize,
 ) -> proc_macro2::TokenStream {
-    use crate::types::get_rc_inner_type;
+    let template = generate_plain_template(wrapper_name, js_name, length);
 
-    // Determine the inner type (unwrap Rc if present)
-    let inner_state_type = if let Some(inner) = get_rc_inner_type(state_type) {
-        inner.clone()
+    quote! {
+        /// Create a FunctionTemplate for this function.
+        ///
+        /// # Example
+        ///
+        /// ```ignore
+        /// let func = my_fn_v8_template(scope).get_function(scope).unwrap();
+        /// ```
+        pub fn #template_fn_name<'s>(
+            scope: &mut v8::PinScope<'s, '_>,
+        ) -> v8::Local<'s, v8::FunctionTemplate> {
+            #template
+        }
+    }
+}
+
+/// Whether the template data owns the state (released by a weak finalizer).
+///
+/// Plain `T` is always owned; `&'static T` never needs releasing.
+pub fn template_owns_state(kind: StateKind, state_mode: StateMode) -> bool {
+    match kind {
+        StateKind::Rc(_) | StateKind::Arc(_) => state_mode == StateMode::Owned,
+        StateKind::Static(_) => false,
+        StateKind::Value(_) => true,
+    }
+}
+
+/// Type of the `state` parameter of the template, function and install helpers
+pub fn state_param_type(kind: StateKind, state_mode: StateMode) -> proc_macro2::TokenStream {
+    let owned = template_owns_state(kind, state_mode);
+
+    match kind {
+        StateKind::Rc(inner) if owned => quote!(std::rc::Rc<#inner>),
+        StateKind::Rc(inner) => quote!(&std::rc::Rc<#inner>),
+        StateKind::Arc(inner) if owned => quote!(std::sync::Arc<#inner>),
+        StateKind::Arc(inner) => quote!(&std::sync::Arc<#inner>),
+        StateKind::Static(inner) => quote!(&'static #inner),
+        StateKind::Value(inner) => quote!(#inner),
+    }
+}
+
+/// Generate statements binding `external`: a `v8::External` pointing to the inner state.
+///
+/// Borrowed `Rc`/`Arc` state and `&'static T` store a plain pointer (the caller
+/// keeps the state alive). Owned state moves a strong count (or the boxed `T`)
+/// into the External, released by a guaranteed finalizer once V8 collects it,
+/// i.e. when no template or function references it, or on isolate disposal.
+pub fn generate_state_external(kind: StateKind, state_mode: StateMode) -> proc_macro2::TokenStream {
+    let inner = kind.inner();
+
+    if !template_owns_state(kind, state_mode) {
+        let ptr = match kind {
+            StateKind::Rc(_) => quote!(std::rc::Rc::as_ptr(state)),
+            StateKind::Arc(_) => quote!(std::sync::Arc::as_ptr(state)),
+            StateKind::Static(_) | StateKind::Value(_) => quote!(state as *const #inner),
+        };
+        return quote! {
+            // SAFETY: The caller keeps the state alive, and the wrappers never
+            // release it (ManuallyDrop / plain references)
+            let ptr = #ptr;
+            let external = v8::External::new(scope, ptr as *mut std::ffi::c_void);
+        };
+    }
+
+    let (into_raw, from_raw) = match kind {
+        StateKind::Rc(_) => (
+            quote!(std::rc::Rc::into_raw(state)),
+            quote!(std::rc::Rc::<#inner>::from_raw(ptr)),
+        ),
+        StateKind::Arc(_) => (
+            quote!(std::sync::Arc::into_raw(state)),
+            quote!(std::sync::Arc::<#inner>::from_raw(ptr)),
+        ),
+        // Only plain `T` is boxed (`&'static T` is never owned)
+        StateKind::Static(_) | StateKind::Value(_) => (
+            quote!(Box::into_raw(Box::new(state))),
+            quote!(Box::<#inner>::from_raw(ptr)),
+        ),
+    };
+
+    let release = generate_guaranteed_release(
+        &quote!(external),
+        &quote!(v8::External),
+        &quote! {
+            // SAFETY: ptr comes from into_raw above, and the finalizer runs once
+            drop(unsafe { #from_raw });
+        },
+    );
+
+    quote! {
+        let ptr = #into_raw;
+        let external = v8::External::new(scope, ptr as *mut std::ffi::c_void);
+
+        // The finalizer releases the state
+        #release
+    }
+}
+
+/// Generate statements running `release` once V8 collects `handle` (a local of
+/// `handle_ty`), or when the isolate is disposed if it is still alive then.
+///
+/// Plain weak finalizers are best-effort and never run on isolate disposal, so
+/// this uses a guaranteed finalizer. It gets no isolate, so the weak handle is
+/// kept in a cell shared with the finalizer, which drops it once run.
+pub fn generate_guaranteed_release(
+    handle: &proc_macro2::TokenStream,
+    handle_ty: &proc_macro2::TokenStream,
+    release: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    quote! {
+        let weak_slot = std::rc::Rc::new(std::cell::Cell::new(None));
+        let finalizer_slot = weak_slot.clone();
+        let weak = v8::Weak::<#handle_ty>::with_guaranteed_finalizer(
+            scope,
+            #handle,
+            Box::new(move || {
+                #release
+                drop(finalizer_slot.take());
+            }),
+        );
+        weak_slot.set(Some(weak));
+    }
+}
+
+/// Generate the `{fn}_v8_template` helper for functions with state passed via External data.
+///
+/// Used for both fast (`cfunction_names` given) and non-fast functions. When the
+/// template owns the state, non-fast functions also get `{fn}_v8_function`,
+/// which creates a function outside the per-context template cache so the state
+/// is released as soon as that function is garbage collected.
+pub fn generate_state_template(
+    fn_name: &syn::Ident,
+    wrapper_name: &syn::Ident,
+    state_type: &Type,
+    state_mode: StateMode,
+    js_name: &str,
+    length: usize,
+    cfunction_names: &[syn::Ident],
+) -> proc_macro2::TokenStream {
+    let is_fast = !cfunction_names.is_empty();
+    let template_fn_name = syn::Ident::new(&format!("{}_v8_template", fn_name), fn_name.span());
+    let function_fn_name = syn::Ident::new(&format!("{}_v8_function", fn_name), fn_name.span());
+    let kind = StateKind::of(state_type);
+    let owned = template_owns_state(kind, state_mode);
+    let state_param = state_param_type(kind, state_mode);
+    let state_external = generate_state_external(kind, state_mode);
+    let length = length as i32;
+    let naming = generate_template_naming(js_name);
+    let raw_name = raw_callback_name(wrapper_name);
+    let build = if is_fast {
+        quote!(build_fast(scope, &[#(#cfunction_names),*]))
     } else {
-        state_type.clone()
+        quote!(build(scope))
     };
 
+    let template_doc = match (owned, is_fast) {
+        (false, false) => quote! {
+            /// Create a FunctionTemplate with state passed via External data.
+            ///
+            /// The state is NOT cloned - the caller must ensure it outlives the function.
+        },
+        (false, true) => quote! {
+            /// Create a FunctionTemplate with both slow and fast paths
+            ///
+            /// IMPORTANT: State is passed via External, NOT context slots.
+            /// The state is NOT cloned - the caller must ensure it outlives the function.
+        },
+        (true, false) => quote! {
+            /// Create a FunctionTemplate owning the state (passed via External data).
+            ///
+            /// The state is dropped after V8 collects the template and the functions
+            /// created from it (functions are cached per context until it is collected),
+            /// or when the isolate is disposed.
+        },
+        (true, true) => quote! {
+            /// Create a FunctionTemplate with both slow and fast paths, owning the state
+            ///
+            /// The state is passed via External data and dropped after V8 collects the
+            /// template and the functions created from it, or when the isolate is disposed.
+        },
+    };
+
+    let function_fn = (owned && !is_fast).then(|| {
+        quote! {
+            /// Create a function owning the state, dropped once the function is collected
+            /// (or when the isolate is disposed).
+            pub fn #function_fn_name<'s>(
+                scope: &mut v8::PinScope<'s, '_>,
+                state: #state_param,
+            ) -> Option<v8::Local<'s, v8::Function>> {
+                #state_external
+
+                let function = v8::Function::builder_raw(#raw_name)
+                    .data(external.into())
+                    .length(#length)
+                    .build(scope)?;
+                let name = v8::String::new(scope, #js_name).unwrap();
+                function.set_name(name);
+                Some(function)
+            }
+        }
+    });
+
     quote! {
-        /// Create a FunctionTemplate with state passed via External data.
+        #template_doc
         ///
         /// # Example
         ///
@@ -268,17 +1114,81 @@ pub fn generate_state_template(
         /// ```
         pub fn #template_fn_name<'s>(
             scope: &mut v8::PinScope<'s, '_>,
-            state: &std::rc::Rc<#inner_state_type>,
+            state: #state_param,
         ) -> v8::Local<'s, v8::FunctionTemplate> {
-            // Create External containing raw pointer to inner state
-            // SAFETY: The Rc ensures the state lives long enough, and we use ManuallyDrop
-            // in the wrapper to avoid double-free
-            let ptr = std::rc::Rc::as_ptr(state);
-            let external = v8::External::new(scope, ptr as *mut std::ffi::c_void);
+            #state_external
 
-            v8::FunctionTemplate::builder(#wrapper_name)
+            let template = v8::FunctionTemplate::builder_raw(#raw_name)
                 .data(external.into())
-                .build(scope)
+                .length(#length)
+                .#build;
+            #naming
+            template
+        }
+
+        #function_fn
+    }
+}
+
+/// Generate the `{fn}_v8_install` helper defining the op on an object under its JS name.
+///
+/// `template` is an expression creating the op's FunctionTemplate; it may use
+/// `scope`, and `state` when the state type and mode are given (typed as in the
+/// template helper, see `state_param_type`).
+pub fn generate_install_fn(
+    fn_name: &syn::Ident,
+    js_name: &str,
+    state: Option<(&Type, StateMode)>,
+    template: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let install_fn_name = syn::Ident::new(&format!("{}_v8_install", fn_name), fn_name.span());
+    let state_param = state.map(|(ty, state_mode)| {
+        let state_ty = state_param_type(StateKind::of(ty), state_mode);
+        quote! { state: #state_ty, }
+    });
+
+    quote! {
+        /// Define this op on `target` under its JS name - auto-generated by glue_v8::method
+        ///
+        /// Returns the created function, or `None` if it could not be created.
+        ///
+        /// # Example
+        ///
+        /// ```ignore
+        /// let global = scope.get_current_context().global(scope);
+        /// my_fn_v8_install(scope, global);
+        /// ```
+        pub fn #install_fn_name<'s>(
+            scope: &mut v8::PinScope<'s, '_>,
+            target: v8::Local<'s, v8::Object>,
+            #state_param
+        ) -> Option<v8::Local<'s, v8::Function>> {
+            let template = #template;
+            let function = template.get_function(scope)?;
+            let key = v8::String::new(scope, #js_name).unwrap();
+            target.set(scope, key.into(), function.into())?;
+            Some(function)
+        }
+    }
+}
+
+/// Generate an expression creating a FunctionTemplate for a stateless slow-path wrapper.
+fn generate_plain_template(
+    wrapper_name: &syn::Ident,
+    js_name: &str,
+    length: usize,
+) -> proc_macro2::TokenStream {
+    let length = length as i32;
+    let naming = generate_template_naming(js_name);
+    let raw_name = raw_callback_name(wrapper_name);
+
+    quote! {
+        {
+            let template = v8::FunctionTemplate::builder_raw(#raw_name)
+                .length(#length)
+                .build(scope);
+            #naming
+            template
         }
     }
 }
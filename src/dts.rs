@@ -0,0 +1,342 @@
+//! TypeScript declaration generation.
+//!
+//! Each op emits a `{FN}_V8_DTS` const holding its `declare function` line,
+//! built from the same parameter list used for argument extraction. Types
+//! without a TypeScript counterpart (user serde types) become `unknown`.
+
+use quote::quote;
+use syn::{ReturnType, Type};
+
+use crate::parse::Int64Repr;
+use crate::types::{
+    get_int64_signedness, get_option_inner_type, get_v8_local_inner_type, required_param_count,
+};
+
+/// Name of the const holding the TypeScript declaration of an op
+pub fn dts_const_name(fn_name: &syn::Ident) -> syn::Ident {
+    syn::Ident::new(
+        &format!("{}_V8_DTS", fn_name.to_string().to_uppercase()),
+        fn_name.span(),
+    )
+}
+
+/// Get the generic arguments of the last path segment if it is named `name`
+fn generic_args<'a>(ty: &'a Type, name: &str) -> Option<Vec<&'a Type>> {
+    if let Type::Path(type_path) = ty
+        && let Some(segment) = type_path.path.segments.last()
+        && segment.ident == name
+        && let syn::PathArguments::AngleBracketed(args) = &segment.arguments
+    {
+        let types = args
+            .args
+            .iter()
+            .filter_map(|arg| match arg {
+                syn::GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            })
+            .collect();
+        return Some(types);
+    }
+    None
+}
+
+/// JavaScript reserved words (including strict mode ones), invalid as parameter
+/// and function names
+pub const JS_RESERVED_WORDS: &[&str] = &[
+    "break",
+    "case",
+    "catch",
+    "class",
+    "const",
+    "continue",
+    "debugger",
+    "default",
+    "delete",
+    "do",
+    "else",
+    "enum",
+    "export",
+    "extends",
+    "false",
+    "finally",
+    "for",
+    "function",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "instanceof",
+    "interface",
+    "let",
+    "new",
+    "null",
+    "package",
+    "private",
+    "protected",
+    "public",
+    "return",
+    "static",
+    "super",
+    "switch",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typeof",
+    "var",
+    "void",
+    "while",
+    "with",
+    "yield",
+];
+
+/// Convert a snake_case Rust identifier to a camelCase JS parameter name
+///
+/// Synthetic names of destructured parameters (`__v8g_arg2`) become `arg2`, and
+/// raw identifiers (`r#type`) lose their prefix. Reserved words get a trailing
+/// underscore (`r#in` becomes `in_`).
+fn camel_case(name: &str) -> String {
+    let mut out = String::new();
+    let mut upper = false;
+    let name = name.strip_prefix("__v8g_").unwrap_or(name);
+    let name = name.strip_prefix("r#").unwrap_or(name);
+
+    for c in name.trim_start_matches('_').chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            out.push(c.to_ascii_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+
+    if JS_RESERVED_WORDS.contains(&out.as_str()) {
+        out.push('_');
+    }
+
+    out
+}
+
+/// Map a V8 Local inner type (e.g. "Function") to its TypeScript type
+fn v8_local_to_ts(v8_type: &str) -> String {
+    match v8_type {
+        "Value" | "Data" => "unknown".to_string(),
+        "Object" => "object".to_string(),
+        "String" => "string".to_string(),
+        "Number" | "Integer" | "Int32" | "Uint32" => "number".to_string(),
+        "BigInt" => "bigint".to_string(),
+        "Boolean" => "boolean".to_string(),
+        "Array" => "unknown[]".to_string(),
+        "Promise" => "Promise<unknown>".to_string(),
+        "Map" => "Map<unknown, unknown>".to_string(),
+        "Set" => "Set<unknown>".to_string(),
+        // Function, ArrayBuffer, Uint8Array, ... share their JS name
+        other => other.to_string(),
+    }
+}
+
+/// Map a Rust type to its TypeScript type (as converted by serde_v8 / V8 Local extraction)
+///
+/// `Option<T>` is `T | null`: serde_v8 serializes `None` as `null`.
+pub fn rust_type_to_ts(ty: &Type) -> String {
+    if let Some(v8_type) = get_v8_local_inner_type(ty) {
+        return v8_local_to_ts(&v8_type);
+    }
+
+    if let Some(inner) = get_option_inner_type(ty) {
+        return format!("{} | null", rust_type_to_ts(inner));
+    }
+
+    match ty {
+        Type::Reference(reference) => return rust_type_to_ts(&reference.elem),
+        Type::Paren(paren) => return rust_type_to_ts(&paren.elem),
+        Type::Slice(slice) => return array_of(&slice.elem),
+        Type::Array(array) => return array_of(&array.elem),
+        Type::Tuple(tuple) if tuple.elems.is_empty() => return "void".to_string(),
+        Type::Tuple(tuple) => {
+            let elems: Vec<_> = tuple.elems.iter().map(rust_type_to_ts).collect();
+            return format!("[{}]", elems.join(", "));
+        }
+        _ => {}
+    }
+
+    for wrapper in ["Box", "Rc", "Arc"] {
+        if let Some(args) = generic_args(ty, wrapper)
+            && let [inner] = args.as_slice()
+        {
+            return rust_type_to_ts(inner);
+        }
+    }
+
+    for seq in ["Vec", "VecDeque", "HashSet", "BTreeSet"] {
+        if let Some(args) = generic_args(ty, seq)
+            && let [inner] = args.as_slice()
+        {
+            return array_of(inner);
+        }
+    }
+
+    for map in ["HashMap", "BTreeMap"] {
+        if let Some(args) = generic_args(ty, map)
+            && let [key, value] = args.as_slice()
+        {
+            return format!(
+                "Record<{}, {}>",
+                rust_type_to_ts(key),
+                rust_type_to_ts(value)
+            );
+        }
+    }
+
+    let Type::Path(type_path) = ty else {
+        return "unknown".to_string();
+    };
+    let Some(segment) = type_path.path.segments.last() else {
+        return "unknown".to_string();
+    };
+
+    match segment.ident.to_string().as_str() {
+        "bool" => "boolean",
+        "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize" | "f32"
+        | "f64" => "number",
+        "String" | "str" | "char" => "string",
+        _ => "unknown",
+    }
+    .to_string()
+}
+
+/// TypeScript type of a parameter or returned value: `i64`/`u64` follow the
+/// op's `int64` representation
+fn value_type_to_ts(ty: &Type, int64: Int64Repr) -> String {
+    if let Some(inner) = get_option_inner_type(ty) {
+        format!("{} | null", value_type_to_ts(inner, int64))
+    } else if int64 == Int64Repr::BigInt && get_int64_signedness(ty).is_some() {
+        "bigint".to_string()
+    } else {
+        rust_type_to_ts(ty)
+    }
+}
+
+/// TypeScript type of a returned value: without `int64`, serde_v8 returns
+/// `i64`/`u64` beyond `Number.MAX_SAFE_INTEGER` as BigInts
+fn returned_type_to_ts(ty: &Type, int64: Int64Repr) -> String {
+    if let Some(inner) = get_option_inner_type(ty) {
+        format!("{} | null", returned_type_to_ts(inner, int64))
+    } else if int64 == Int64Repr::Serde && get_int64_signedness(ty).is_some() {
+        "number | bigint".to_string()
+    } else {
+        value_type_to_ts(ty, int64)
+    }
+}
+
+/// TypeScript array type, parenthesizing unions
+fn array_of(elem: &Type) -> String {
+    let elem = rust_type_to_ts(elem);
+
+    if elem.contains(' ') {
+        format!("({})[]", elem)
+    } else {
+        format!("{}[]", elem)
+    }
+}
+
+/// TypeScript return type: `Result<T, E>` unwraps to `T`, promises wrap in `Promise<T>`
+fn return_type_to_ts(output: &ReturnType, is_promise: bool, int64: Int64Repr) -> String {
+    let ts = match output {
+        ReturnType::Default => "void".to_string(),
+        ReturnType::Type(_, ty) => match generic_args(ty, "Result") {
+            Some(args) if !args.is_empty() => returned_type_to_ts(args[0], int64),
+            _ => returned_type_to_ts(ty, int64),
+        },
+    };
+
+    if is_promise {
+        format!("Promise<{}>", ts)
+    } else {
+        ts
+    }
+}
+
+/// Build the `declare function` line for an op.
+///
+/// Trailing `Option<T>` parameters become optional (`name?: T`); other
+/// `Option<T>` parameters are typed `T | null | undefined` (both convert to
+/// `None`).
+pub fn function_declaration(
+    js_name: &str,
+    params: &[(syn::Ident, Box<Type>)],
+    output: &ReturnType,
+    is_promise: bool,
+    int64: Int64Repr,
+) -> String {
+    let required = required_param_count(params);
+
+    let params: Vec<_> = params
+        .iter()
+        .enumerate()
+        .map(|(i, (name, ty))| match get_option_inner_type(ty) {
+            Some(inner) if i >= required => {
+                format!(
+                    "{}?: {}",
+                    camel_case(&name.to_string()),
+                    value_type_to_ts(inner, int64)
+                )
+            }
+            _ => {
+                let ts = match get_option_inner_type(ty) {
+                    Some(inner) => {
+                        format!("{} | null | undefined", value_type_to_ts(inner, int64))
+                    }
+                    None => value_type_to_ts(ty, int64),
+                };
+                format!("{}: {}", camel_case(&name.to_string()), ts)
+            }
+        })
+        .collect();
+
+    format!(
+        "declare function {}({}): {};",
+        js_name,
+        params.join(", "),
+        return_type_to_ts(output, is_promise, int64)
+    )
+}
+
+/// Generate the `{FN}_V8_DTS` const for an op.
+pub fn generate_dts_const(
+    fn_name: &syn::Ident,
+    js_name: &str,
+    params: &[(syn::Ident, Box<Type>)],
+    output: &ReturnType,
+    is_promise: bool,
+    int64: Int64Repr,
+) -> proc_macro2::TokenStream {
+    let const_name = dts_const_name(fn_name);
+    let declaration = function_declaration(js_name, params, output, is_promise, int64);
+
+    quote! {
+        /// TypeScript declaration - auto-generated by glue_v8::method
+        pub const #const_name: &str = #declaration;
+    }
+}
+
+/// Expand `glue_v8::dts!(path::to::op, ...)` to a `String` joining the ops' declarations.
+pub fn generate_dts_collection(ops: &[syn::Path]) -> proc_macro2::TokenStream {
+    let consts = ops.iter().map(|op| {
+        let mut path = op.clone();
+        let last = path.segments.last_mut().unwrap();
+        last.ident = dts_const_name(&last.ident);
+        path
+    });
+
+    quote! {
+        {
+            let declarations: &[&str] = &[#(#consts),*];
+            let mut dts = declarations.join("\n");
+            dts.push('\n');
+            dts
+        }
+    }
+}
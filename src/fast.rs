@@ -6,7 +6,17 @@
 use quote::quote;
 use syn::{ItemFn, ReturnType, Type};
 
-use crate::types::get_rc_inner_type;
+use crate::codegen::{
+    MAX_SAFE_INTEGER, ReturnConversion, UNSAFE_RETURN_MESSAGE, arg_label, context_state_slot,
+    generate_install_fn, generate_plain_template_fn, generate_state_borrow_mut,
+    generate_state_template, generate_template_naming, return_conversion, state_not_bound_message,
+    state_slot, unsafe_arg_message,
+};
+use crate::parse::{Int64Repr, StateMode};
+use crate::snapshot::{generate_external_refs, raw_callback_name};
+use crate::types::{
+    StateKind, get_option_inner_type, get_result_ok_type, is_str_ref_type, required_param_count,
+};
 
 /// V8 Fast API type mapping
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -19,6 +29,9 @@ pub enum FastApiType {
     U64,
     F32,
     F64,
+    /// `&str` param: a sequential one-byte (Latin-1) string. Other strings take
+    /// the slow path.
+    OneByteString,
 }
 
 impl FastApiType {
@@ -33,6 +46,7 @@ impl FastApiType {
             FastApiType::U64 => quote!(v8::fast_api::Type::Uint64.as_info()),
             FastApiType::F32 => quote!(v8::fast_api::Type::Float32.as_info()),
             FastApiType::F64 => quote!(v8::fast_api::Type::Float64.as_info()),
+            FastApiType::OneByteString => quote!(v8::fast_api::Type::SeqOneByteString.as_info()),
         }
     }
 
@@ -47,44 +61,480 @@ impl FastApiType {
             FastApiType::U64 => quote!(u64),
             FastApiType::F32 => quote!(f32),
             FastApiType::F64 => quote!(f64),
+            FastApiType::OneByteString => quote!(*const v8::fast_api::FastApiOneByteString),
+        }
+    }
+
+    /// Convert the fast call argument `name` to the type the op takes, if needed
+    pub fn quote_conversion(&self, name: &syn::Ident) -> Option<proc_macro2::TokenStream> {
+        match self {
+            FastApiType::OneByteString => Some(quote! {
+                // SAFETY: V8 passes a valid string for the duration of the call
+                let #name = unsafe { &*#name }.as_bytes();
+                // One-byte strings are Latin-1: ASCII is valid UTF-8 as is, the
+                // rest needs re-encoding
+                let #name: std::borrow::Cow<str> = if #name.is_ascii() {
+                    // SAFETY: checked to be ASCII
+                    std::borrow::Cow::Borrowed(unsafe { std::str::from_utf8_unchecked(#name) })
+                } else {
+                    std::borrow::Cow::Owned(#name.iter().map(|&b| b as char).collect())
+                };
+                let #name: &str = &#name;
+            }),
+            _ => None,
         }
     }
 }
 
 /// Check if a type is Fast API compatible and return the mapping
 pub fn get_fast_api_type(ty: &Type) -> Option<FastApiType> {
-    if let Type::Path(type_path) = ty {
-        if let Some(segment) = type_path.path.segments.last() {
-            let ident = segment.ident.to_string();
-
-            return match ident.as_str() {
-                "bool" => Some(FastApiType::Bool),
-                "i32" => Some(FastApiType::I32),
-                "u32" => Some(FastApiType::U32),
-                "i64" => Some(FastApiType::I64),
-                "u64" => Some(FastApiType::U64),
-                "f32" => Some(FastApiType::F32),
-                "f64" => Some(FastApiType::F64),
-                _ => None,
-            };
-        }
+    if is_str_ref_type(ty) {
+        return Some(FastApiType::OneByteString);
+    }
+
+    if let Type::Path(type_path) = ty
+        && let Some(segment) = type_path.path.segments.last()
+    {
+        let ident = segment.ident.to_string();
+
+        return match ident.as_str() {
+            "bool" => Some(FastApiType::Bool),
+            "i32" => Some(FastApiType::I32),
+            "u32" => Some(FastApiType::U32),
+            "i64" => Some(FastApiType::I64),
+            "u64" => Some(FastApiType::U64),
+            "f32" => Some(FastApiType::F32),
+            "f64" => Some(FastApiType::F64),
+            _ => None,
+        };
     }
 
     // Handle unit type ()
-    if let Type::Tuple(tuple) = ty {
-        if tuple.elems.is_empty() {
-            return Some(FastApiType::Void);
-        }
+    if let Type::Tuple(tuple) = ty
+        && tuple.elems.is_empty()
+    {
+        return Some(FastApiType::Void);
     }
 
     None
 }
 
-/// Get the Fast API return type from a function's return type
+/// Get the Fast API return type from a function's return type (the `T` of `Result<T, E>`)
 pub fn get_fast_api_return_type(ret: &ReturnType) -> Option<FastApiType> {
     match ret {
         ReturnType::Default => Some(FastApiType::Void),
-        ReturnType::Type(_, ty) => get_fast_api_type(ty),
+        ReturnType::Type(_, ty) => get_fast_api_type(get_result_ok_type(ty).unwrap_or(ty))
+            .filter(|ty| *ty != FastApiType::OneByteString),
+    }
+}
+
+/// Get the Fast API type of a parameter.
+///
+/// `optional` params (trailing `Option`s, which may be omitted) of a number or
+/// bool type pass their inner type, see `generate_fast_overloads`.
+fn get_fast_api_param_type(ty: &Type, optional: bool) -> Option<FastApiType> {
+    match get_option_inner_type(ty) {
+        Some(inner) if optional => {
+            get_fast_api_type(inner).filter(|ty| *ty != FastApiType::OneByteString)
+        }
+        Some(_) => None,
+        None => get_fast_api_type(ty),
+    }
+}
+
+/// Find why an op can't get a Fast API path, as an error spanning the parameter
+/// or return type responsible.
+///
+/// The fast path has no scope (so no scope, isolate or `#[state]` params), can't
+/// create a promise, and only passes the primitives of `FastApiType`.
+fn fast_api_blocker(
+    sig: &syn::Signature,
+    params: &[(syn::Ident, Box<Type>)],
+    scope_param: Option<&syn::Ident>,
+    is_promise: bool,
+) -> Option<syn::Error> {
+    if let Some(name) = scope_param {
+        return Some(syn::Error::new_spanned(
+            name,
+            format!(
+                "`fast(require)`: parameter `{}` needs the callback scope, which the Fast API path does not have",
+                name
+            ),
+        ));
+    }
+
+    if is_promise {
+        return Some(syn::Error::new_spanned(
+            &sig.ident,
+            "`fast(require)`: promise ops cannot use the Fast API path",
+        ));
+    }
+
+    let required = required_param_count(params);
+    for (idx, (name, ty)) in params.iter().enumerate() {
+        if get_fast_api_param_type(ty, idx >= required).is_none() {
+            return Some(syn::Error::new_spanned(
+                ty,
+                format!(
+                    "`fast(require)`: parameter `{}` has no Fast API type (expected bool, i32, u32, i64, u64, f32, f64 or &str, or a trailing `Option` of a number or bool)",
+                    name
+                ),
+            ));
+        }
+    }
+
+    match &sig.output {
+        ReturnType::Type(_, ty) if get_fast_api_return_type(&sig.output).is_none() => {
+            Some(syn::Error::new_spanned(
+                ty,
+                "`fast(require)`: the return type has no Fast API type (expected (), bool, i32, u32, i64, u64, f32 or f64, or a `Result` of one)",
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Require an explicit `int64 = ..` when a fast op passes `i64`/`u64`.
+///
+/// Without it the slow path converts them via serde_v8 (returning a BigInt beyond
+/// `Number.MAX_SAFE_INTEGER`), which the Fast API path can't do, so an optimized
+/// call would see a different representation. The error spans the first 64-bit
+/// parameter or return type.
+fn missing_int64_repr(
+    sig: &syn::Signature,
+    params: &[(syn::Ident, Box<Type>)],
+    int64: Int64Repr,
+) -> Option<syn::Error> {
+    if int64 != Int64Repr::Serde {
+        return None;
+    }
+
+    let required = required_param_count(params);
+    let is_int64 =
+        |ty: Option<FastApiType>| matches!(ty, Some(FastApiType::I64 | FastApiType::U64));
+    let param = params
+        .iter()
+        .enumerate()
+        .find(|(idx, (_, ty))| is_int64(get_fast_api_param_type(ty, *idx >= required)))
+        .map(|(_, (_, ty))| &**ty);
+    let output = match &sig.output {
+        ReturnType::Type(_, ty) if is_int64(get_fast_api_return_type(&sig.output)) => Some(&**ty),
+        _ => None,
+    };
+
+    param.or(output).map(|ty| {
+        syn::Error::new_spanned(
+            ty,
+            "`fast` ops with `i64`/`u64` need `int64 = \"number\"` or `int64 = \"bigint\"`, so the slow and fast paths use the same representation",
+        )
+    })
+}
+
+/// Conversions of the fast call arguments to the op's parameter types.
+///
+/// With `int64 = "number"`, `i64`/`u64` arguments beyond the safe integers
+/// throw the slow path's RangeError.
+fn fast_param_conversions(
+    params: &[(syn::Ident, Box<Type>)],
+    fast_param_types: &[FastApiType],
+    int64: Int64Repr,
+) -> Vec<proc_macro2::TokenStream> {
+    params
+        .iter()
+        .zip(fast_param_types)
+        .enumerate()
+        .map(|(idx, ((name, ty), fast_ty))| {
+            let conversion = fast_ty.quote_conversion(name);
+            let range_check = match fast_ty {
+                FastApiType::I64 | FastApiType::U64 if int64 == Int64Repr::Number => {
+                    let msg = unsafe_arg_message(&arg_label(idx), *fast_ty == FastApiType::I64);
+                    let throw = fast_throw(quote!(range_error), quote!(#msg));
+                    Some(quote! {
+                        if (#name as i128).unsigned_abs() > #MAX_SAFE_INTEGER as u128 {
+                            #throw
+                            return Default::default();
+                        }
+                    })
+                }
+                _ => None,
+            };
+            let wrap = get_option_inner_type(ty).map(|_| quote!(let #name = Some(#name);));
+            quote!(#conversion #range_check #wrap)
+        })
+        .collect()
+}
+
+/// Per-op call counters of the `fast-counters` feature, to check that V8
+/// actually takes the Fast API path: `{PREFIX}_V8_SLOW_CALLS` and
+/// `{PREFIX}_V8_FAST_CALLS` statics, incremented by the slow wrapper and the
+/// fast functions. Like the CFunctions, the fast counters of overloads shorter
+/// than the full arity get an `_{arity}` suffix. Without the feature, nothing
+/// is generated.
+///
+/// `cfg!` is evaluated in this proc macro, whose features cargo unifies across
+/// the build: the counters follow the feature of glue_v8, not of the crate
+/// expanding the op.
+pub struct CallCounters {
+    slow: syn::Ident,
+    /// Fast counter of each overload, by arity
+    fast: Vec<(usize, syn::Ident)>,
+}
+
+impl CallCounters {
+    pub fn new(prefix: &str, span: proc_macro2::Span, params: &[(syn::Ident, Box<Type>)]) -> Self {
+        let fast = (required_param_count(params)..=params.len())
+            .map(|arity| {
+                let suffix = if arity == params.len() {
+                    String::new()
+                } else {
+                    format!("_{}", arity)
+                };
+                let name = format!("{}_V8_FAST_CALLS{}", prefix, suffix);
+                (arity, syn::Ident::new(&name, span))
+            })
+            .collect();
+
+        Self {
+            slow: syn::Ident::new(&format!("{}_V8_SLOW_CALLS", prefix), span),
+            fast,
+        }
+    }
+
+    /// The counter statics
+    pub fn statics(&self) -> proc_macro2::TokenStream {
+        if !cfg!(feature = "fast-counters") {
+            return quote!();
+        }
+
+        let slow = &self.slow;
+        let fast = self.fast.iter().map(|(arity, counter)| {
+            let doc = format!(
+                "Fast API path calls with {} arguments - auto-generated by glue_v8 (`fast-counters` feature)",
+                arity
+            );
+            quote! {
+                #[doc = #doc]
+                pub static #counter: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+            }
+        });
+        quote! {
+            /// Slow path calls - auto-generated by glue_v8 (`fast-counters` feature)
+            pub static #slow: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+            #(#fast)*
+        }
+    }
+
+    /// Count a call of the slow wrapper
+    pub fn count_slow(&self) -> proc_macro2::TokenStream {
+        Self::count(&self.slow)
+    }
+
+    /// Count a call of the fast function taking `arity` arguments
+    fn count_fast(&self, arity: usize) -> proc_macro2::TokenStream {
+        let (_, counter) = self
+            .fast
+            .iter()
+            .find(|(counter_arity, _)| *counter_arity == arity)
+            .expect("one fast counter per overload arity");
+        Self::count(counter)
+    }
+
+    fn count(counter: &syn::Ident) -> proc_macro2::TokenStream {
+        if !cfg!(feature = "fast-counters") {
+            return quote!();
+        }
+
+        quote!(#counter.fetch_add(1, std::sync::atomic::Ordering::Relaxed);)
+    }
+}
+
+/// Names of the fast functions, CFunctionInfo and CFunction consts of the
+/// overload taking `arity` arguments
+fn fast_overload_names(
+    fn_name: &syn::Ident,
+    arity: usize,
+    full_arity: bool,
+) -> (syn::Ident, syn::Ident, syn::Ident) {
+    let suffix = if full_arity {
+        String::new()
+    } else {
+        format!("_{}", arity)
+    };
+    let upper = fn_name.to_string().to_uppercase();
+
+    (
+        syn::Ident::new(&format!("{}_v8_fast{}", fn_name, suffix), fn_name.span()),
+        syn::Ident::new(
+            &format!("{}_V8_FAST_CALL{}_INFO", upper, suffix),
+            fn_name.span(),
+        ),
+        syn::Ident::new(&format!("{}_V8_FAST_CALL{}", upper, suffix), fn_name.span()),
+    )
+}
+
+/// CFunction consts of an op, one per overload (see `generate_fast_overloads`)
+fn fast_cfunction_names(
+    fn_name: &syn::Ident,
+    params: &[(syn::Ident, Box<Type>)],
+) -> Vec<syn::Ident> {
+    (required_param_count(params)..=params.len())
+        .rev()
+        .map(|arity| fast_overload_names(fn_name, arity, arity == params.len()).2)
+        .collect()
+}
+
+/// Generate the fast functions of an op with their CFunctionInfo and CFunction
+/// consts.
+///
+/// Trailing `Option` params may be omitted from JS, so there is one overload
+/// per arity (V8 picks the one matching the number of arguments), passing
+/// `None` for the omitted params. The full-arity overload is `{fn}_v8_fast` /
+/// `{FN}_V8_FAST_CALL`, shorter ones get an `_{arity}` suffix.
+///
+/// `state_resolution` runs first in each fast function, and `callee` is called
+/// with `call_args`. `item_path` prefixes the references between the generated
+/// items (`Self::` in an impl block, empty otherwise).
+#[allow(clippy::too_many_arguments)]
+fn generate_fast_overloads(
+    fn_name: &syn::Ident,
+    params: &[(syn::Ident, Box<Type>)],
+    fast_param_types: &[FastApiType],
+    fast_return: FastApiType,
+    callee: &proc_macro2::TokenStream,
+    call_args: &[proc_macro2::TokenStream],
+    output: &ReturnType,
+    int64: Int64Repr,
+    fast_doc: &proc_macro2::TokenStream,
+    state_resolution: &proc_macro2::TokenStream,
+    item_path: &proc_macro2::TokenStream,
+    counters: &CallCounters,
+) -> proc_macro2::TokenStream {
+    // Fast API signature: receiver (V8Value) + user args + CallbackOptions
+    let receiver_ctype = quote!(v8::fast_api::Type::V8Value.as_info());
+    let options_ctype = quote!(v8::fast_api::Type::CallbackOptions.as_info());
+    let return_ctype = fast_return.quote_ctype();
+    let fast_return_rust = fast_return.quote_rust_type();
+    let fast_call = generate_fast_call(callee, call_args, output, int64);
+    // `Serde` only reaches here without 64-bit integers (see `missing_int64_repr`)
+    let int64_repr = match int64 {
+        Int64Repr::Serde | Int64Repr::Number => quote!(Number),
+        Int64Repr::BigInt => quote!(BigInt),
+    };
+
+    let overloads = (required_param_count(params)..=params.len()).rev().map(|arity| {
+        let (fast_fn_name, cfunction_info_name, cfunction_name) =
+            fast_overload_names(fn_name, arity, arity == params.len());
+        let (passed, omitted) = params.split_at(arity);
+        let passed_types = &fast_param_types[..arity];
+
+        let arg_ctypes = passed_types.iter().map(|t| t.quote_ctype());
+        let fast_params = passed.iter().zip(passed_types).map(|((name, _), fast_ty)| {
+            let rust_type = fast_ty.quote_rust_type();
+            quote!(#name: #rust_type)
+        });
+        let conversions = fast_param_conversions(passed, passed_types, int64);
+        let count_fast = counters.count_fast(arity);
+        let omitted_args = omitted
+            .iter()
+            .map(|(name, ty)| quote!(let #name: #ty = None;));
+
+        quote! {
+            #fast_doc
+            extern "C" fn #fast_fn_name<'s>(
+                __v8g_recv: v8::Local<v8::Value>,
+                #(#fast_params,)*
+                __v8g_options: *mut v8::fast_api::FastApiCallbackOptions<'s>,
+            ) -> #fast_return_rust {
+                #count_fast
+                #state_resolution
+                #(#conversions)*
+                #(#omitted_args)*
+
+                #fast_call
+            }
+
+            /// CFunctionInfo for the fast call signature
+            const #cfunction_info_name: v8::fast_api::CFunctionInfo = v8::fast_api::CFunctionInfo::new(
+                #return_ctype,
+                &[#receiver_ctype, #(#arg_ctypes,)* #options_ctype],
+                v8::fast_api::Int64Representation::#int64_repr,
+            );
+
+            /// CFunction definition for V8 Fast API
+            pub const #cfunction_name: v8::fast_api::CFunction = v8::fast_api::CFunction::new(
+                #item_path #fast_fn_name as *const std::ffi::c_void,
+                &#item_path #cfunction_info_name,
+            );
+        }
+    });
+
+    quote!(#(#overloads)*)
+}
+
+/// Throw `v8::Exception::{exception}(msg)` from a fast function, through the
+/// isolate of the callback options
+fn fast_throw(
+    exception: proc_macro2::TokenStream,
+    msg: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    quote! {
+        // SAFETY: options is valid during fast call, and carries the current isolate
+        let scope = std::pin::pin!(unsafe { v8::CallbackScope::new(&*__v8g_options) });
+        let scope = scope.init();
+        let msg = v8::String::new(&scope, #msg).unwrap();
+        let error = v8::Exception::#exception(&scope, msg);
+        scope.throw_exception(error);
+    }
+}
+
+/// Call the op from the fast path.
+///
+/// Errors throw the same exceptions as the slow path, through the isolate of
+/// the callback options, and return a default value (ignored by V8): the `Err`
+/// of a `Result` op, and an `i64`/`u64` a Number can't hold exactly.
+fn generate_fast_call(
+    callee: &proc_macro2::TokenStream,
+    call_args: &[proc_macro2::TokenStream],
+    output: &ReturnType,
+    int64: Int64Repr,
+) -> proc_macro2::TokenStream {
+    let returns_result =
+        matches!(output, ReturnType::Type(_, ty) if get_result_ok_type(ty).is_some());
+
+    let call = if returns_result {
+        let throw = fast_throw(quote!(error), quote!(&format!("{}", err)));
+        quote! {
+            match #callee(#(#call_args),*) {
+                Ok(value) => value,
+                Err(err) => {
+                    #throw
+                    return Default::default();
+                }
+            }
+        }
+    } else {
+        quote!(#callee(#(#call_args),*))
+    };
+
+    if !matches!(
+        return_conversion(output, int64),
+        ReturnConversion::Int64 {
+            repr: Int64Repr::Number,
+            ..
+        }
+    ) {
+        return call;
+    }
+
+    let throw = fast_throw(quote!(range_error), quote!(#UNSAFE_RETURN_MESSAGE));
+    quote! {
+        let __v8g_int = #call;
+        if (__v8g_int as i128).unsigned_abs() > #MAX_SAFE_INTEGER as u128 {
+            #throw
+            return Default::default();
+        }
+        __v8g_int
     }
 }
 
@@ -99,120 +549,140 @@ pub fn get_fast_api_return_type(ret: &ReturnType) -> Option<FastApiType> {
 /// - Slow path: extracts from args.data()
 /// - Fast path: extracts from options.data
 ///
-/// Important: Fast API with state does NOT use context slots.
-/// The state must be passed when creating the FunctionTemplate.
+/// Ops that can't get a Fast API path (see `fast_api_blocker`) fall back to the
+/// slow path only, with the same `{fn}_v8_template` and `{fn}_v8_install` helpers
+/// as without `fast`. With `fast(require)` (`fast_required`), the blocker is a
+/// compile error instead. `scope_param` is the first parameter resolved through
+/// the scope (scope, isolate or `#[state]`), which the fast path doesn't have.
 #[allow(clippy::too_many_arguments)]
 pub fn generate_fast_api_code(
     input_fn: &ItemFn,
     fn_name: &syn::Ident,
     wrapper_name: &syn::Ident,
     params: &[(syn::Ident, Box<Type>)],
-    has_scope: bool,
+    scope_param: Option<&syn::Ident>,
+    is_promise: bool,
+    fast_required: bool,
     has_state: bool,
     state_type: &Option<Type>,
     state_extraction: &proc_macro2::TokenStream,
     arg_extractions: &[proc_macro2::TokenStream],
+    call_args: &[proc_macro2::TokenStream],
     call_and_return: &proc_macro2::TokenStream,
+    js_name: &str,
+    length: usize,
+    state_mode: StateMode,
+    state_mut: bool,
+    int64: Int64Repr,
 ) -> proc_macro2::TokenStream {
-    let fast_fn_name = syn::Ident::new(&format!("{}_v8_fast", fn_name), fn_name.span());
     let template_fn_name = syn::Ident::new(&format!("{}_v8_template", fn_name), fn_name.span());
-    let cfunction_name = syn::Ident::new(
-        &format!("{}_V8_FAST_CALL", fn_name.to_string().to_uppercase()),
-        fn_name.span(),
-    );
-    let cfunction_info_name = syn::Ident::new(
-        &format!("{}_V8_FAST_CALL_INFO", fn_name.to_string().to_uppercase()),
-        fn_name.span(),
-    );
+    let counters = CallCounters::new(&fn_name.to_string().to_uppercase(), fn_name.span(), params);
 
-    // Check if all params are Fast API compatible
-    let mut fast_param_types: Vec<FastApiType> = Vec::new();
-    let mut all_fast_compatible = true;
-
-    for (_, ty) in params {
-        if let Some(fast_type) = get_fast_api_type(ty) {
-            fast_param_types.push(fast_type);
-        } else {
-            all_fast_compatible = false;
-            break;
+    // Registration helpers for the slow-path-only fallbacks
+    let fallback_external_refs = generate_external_refs(fn_name, wrapper_name, &[]);
+    let fallback_registration = match state_type {
+        Some(state_ty) if has_state && !state_mode.is_slot() => {
+            let template_fn = generate_state_template(
+                fn_name,
+                wrapper_name,
+                state_ty,
+                state_mode,
+                js_name,
+                length,
+                &[],
+            );
+            let install_fn = generate_install_fn(
+                fn_name,
+                js_name,
+                Some((state_ty, state_mode)),
+                &quote!(#template_fn_name(scope, state)),
+            );
+            quote! {
+                #template_fn
+                #install_fn
+            }
         }
-    }
+        _ => {
+            let template_fn =
+                generate_plain_template_fn(wrapper_name, &template_fn_name, js_name, length);
+            let install_fn =
+                generate_install_fn(fn_name, js_name, None, &quote!(#template_fn_name(scope)));
+            quote! {
+                #template_fn
+                #install_fn
+            }
+        }
+    };
 
-    // Check return type
-    let fast_return_type = get_fast_api_return_type(&input_fn.sig.output);
+    if let Some(blocker) = fast_api_blocker(&input_fn.sig, params, scope_param, is_promise) {
+        if fast_required {
+            return blocker.to_compile_error();
+        }
 
-    if !all_fast_compatible || fast_return_type.is_none() {
-        // Fall back to slow-path only
+        // Fall back to slow path only, with the same helpers as a non-fast op
+        let count_slow = counters.count_slow();
+        let counter_statics = counters.statics();
         return quote! {
             #input_fn
 
-            // Note: fast attribute specified but function has non-primitive types.
-            // Falling back to slow path only.
-
             /// V8 callback wrapper - auto-generated by glue_v8::method
             pub fn #wrapper_name(
-                scope: &mut v8::PinScope,
-                args: v8::FunctionCallbackArguments,
-                mut rv: v8::ReturnValue,
+                __v8g_scope: &mut v8::PinScope,
+                __v8g_args: v8::FunctionCallbackArguments,
+                mut __v8g_rv: v8::ReturnValue,
             ) {
+                #count_slow
                 #state_extraction
                 #(#arg_extractions)*
                 #call_and_return
             }
-        };
-    }
 
-    // Fast API is not compatible with functions that use scope
-    // (they need scope for V8 operations which isn't available in fast path)
-    if has_scope {
-        return quote! {
-            #input_fn
+            #fallback_registration
 
-            // Note: fast attribute specified but function uses scope.
-            // Fast API cannot provide scope access. Falling back to slow path only.
+            #fallback_external_refs
 
-            /// V8 callback wrapper - auto-generated by glue_v8::method
-            pub fn #wrapper_name(
-                scope: &mut v8::PinScope,
-                args: v8::FunctionCallbackArguments,
-                mut rv: v8::ReturnValue,
-            ) {
-                #state_extraction
-                #(#arg_extractions)*
-                #call_and_return
-            }
+            #counter_statics
         };
     }
 
-    // Common: get return type
-    let fast_return = fast_return_type.unwrap();
+    if let Some(err) = missing_int64_repr(&input_fn.sig, params, int64) {
+        return err.to_compile_error();
+    }
 
-    // Fast API WITH state: use options.data to extract state
-    if has_state {
-        if let Some(state_ty) = state_type {
-            return generate_fast_api_with_state(
-                input_fn,
-                fn_name,
-                wrapper_name,
-                &fast_fn_name,
-                &template_fn_name,
-                &cfunction_name,
-                &cfunction_info_name,
-                params,
-                &fast_param_types,
-                fast_return,
-                state_ty,
-                arg_extractions,
-                call_and_return,
-            );
-        } else {
-            // State without type - compilation error
-            return quote! {
-                #input_fn
+    let required = required_param_count(params);
+    let fast_param_types: Vec<FastApiType> = params
+        .iter()
+        .enumerate()
+        .map(|(idx, (_, ty))| {
+            get_fast_api_param_type(ty, idx >= required).expect("checked by fast_api_blocker")
+        })
+        .collect();
+    let fast_return =
+        get_fast_api_return_type(&input_fn.sig.output).expect("checked by fast_api_blocker");
 
-                compile_error!("Function has 'state' parameter but no state type specified. Use #[glue_v8::method(fast, state = YourStateType)]");
-            };
-        }
+    // Fast API WITH state: use options.data to extract state
+    // (a missing state type is reported by `generate_state_extraction`)
+    if has_state && let Some(state_ty) = state_type {
+        return generate_fast_api_with_state(
+            input_fn,
+            fn_name,
+            wrapper_name,
+            &template_fn_name,
+            params,
+            &fast_param_types,
+            fast_return,
+            state_ty,
+            state_extraction,
+            arg_extractions,
+            call_args,
+            call_and_return,
+            js_name,
+            length,
+            state_mode,
+            state_mut,
+            int64,
+            &counters,
+        );
     }
 
     // Pure function without scope or state - simplest Fast API case
@@ -220,96 +690,183 @@ pub fn generate_fast_api_code(
         input_fn,
         fn_name,
         wrapper_name,
-        &fast_fn_name,
         &template_fn_name,
-        &cfunction_name,
-        &cfunction_info_name,
         params,
         &fast_param_types,
         fast_return,
         state_extraction,
         arg_extractions,
+        call_args,
         call_and_return,
+        js_name,
+        length,
+        int64,
+        &counters,
     )
 }
 
+/// Generate the fast functions of a `#[method(fast)]` class method.
+///
+/// The receiver is brand-checked and borrowed as in the slow path: V8 only
+/// takes the fast path for receivers matching the method's signature, which
+/// leaves foreign receivers to the slow path's TypeError, and the fast path
+/// throws the same errors if it gets one anyway. `call_args` start with the
+/// borrowed `__v8g_self`.
+///
+/// Returns the fast functions (for the class impl block) with their CFunction
+/// consts, or `None` when the method can't use the Fast API path (see
+/// `fast_api_blocker`), which is a compile error with `fast(require)`.
+#[allow(clippy::too_many_arguments)]
+pub fn generate_fast_method(
+    sig: &syn::Signature,
+    params: &[(syn::Ident, Box<Type>)],
+    scope_param: Option<&syn::Ident>,
+    is_promise: bool,
+    fast_required: bool,
+    callee: &proc_macro2::TokenStream,
+    call_args: &[proc_macro2::TokenStream],
+    unwrap_fn: &syn::Ident,
+    receiver_mut: bool,
+    borrow_error: &str,
+    int64: Int64Repr,
+    counters: &CallCounters,
+) -> syn::Result<Option<(proc_macro2::TokenStream, Vec<syn::Ident>)>> {
+    if let Some(blocker) = fast_api_blocker(sig, params, scope_param, is_promise) {
+        return if fast_required {
+            Err(blocker)
+        } else {
+            Ok(None)
+        };
+    }
+    if let Some(err) = missing_int64_repr(sig, params, int64) {
+        return Err(err);
+    }
+
+    let required = required_param_count(params);
+    let fast_param_types: Vec<FastApiType> = params
+        .iter()
+        .enumerate()
+        .map(|(idx, (_, ty))| {
+            get_fast_api_param_type(ty, idx >= required).expect("checked by fast_api_blocker")
+        })
+        .collect();
+    let fast_return = get_fast_api_return_type(&sig.output).expect("checked by fast_api_blocker");
+
+    let fn_name = &sig.ident;
+    let illegal_invocation = fast_throw(quote!(type_error), quote!("Illegal invocation"));
+    let borrow_failed = fast_throw(quote!(error), quote!(#borrow_error));
+    let borrow = if receiver_mut {
+        quote! { let Ok(mut __v8g_self) = __v8g_cell.try_borrow_mut() }
+    } else {
+        quote! { let Ok(__v8g_self) = __v8g_cell.try_borrow() }
+    };
+    let receiver_resolution = quote! {
+        let __v8g_cell = {
+            // SAFETY: options is valid during fast call, and carries the current isolate
+            let scope = std::pin::pin!(unsafe { v8::CallbackScope::new(&*__v8g_options) });
+            let scope = scope.init();
+            v8::Local::<v8::Object>::try_from(__v8g_recv)
+                .ok()
+                .and_then(|object| #unwrap_fn(&scope, object))
+        };
+        let Some(__v8g_cell) = __v8g_cell else {
+            #illegal_invocation
+            return Default::default();
+        };
+        #borrow else {
+            #borrow_failed
+            return Default::default();
+        };
+    };
+    let fast_doc = quote! {
+        /// V8 Fast API callback - auto-generated by glue_v8::methods
+        ///
+        /// This is called directly by V8's optimizing compiler for hot paths.
+        /// The wrapped value is read from the receiver's internal field.
+    };
+    let fast_fns = generate_fast_overloads(
+        fn_name,
+        params,
+        &fast_param_types,
+        fast_return,
+        callee,
+        call_args,
+        &sig.output,
+        int64,
+        &fast_doc,
+        &receiver_resolution,
+        &quote!(Self::),
+        counters,
+    );
+
+    Ok(Some((fast_fns, fast_cfunction_names(fn_name, params))))
+}
+
 /// Generate Fast API for pure functions (no scope, no state)
 #[allow(clippy::too_many_arguments)]
 fn generate_fast_api_pure(
     input_fn: &ItemFn,
     fn_name: &syn::Ident,
     wrapper_name: &syn::Ident,
-    fast_fn_name: &syn::Ident,
     template_fn_name: &syn::Ident,
-    cfunction_name: &syn::Ident,
-    cfunction_info_name: &syn::Ident,
     params: &[(syn::Ident, Box<Type>)],
     fast_param_types: &[FastApiType],
     fast_return: FastApiType,
     state_extraction: &proc_macro2::TokenStream,
     arg_extractions: &[proc_macro2::TokenStream],
+    call_args: &[proc_macro2::TokenStream],
     call_and_return: &proc_macro2::TokenStream,
+    js_name: &str,
+    length: usize,
+    int64: Int64Repr,
+    counters: &CallCounters,
 ) -> proc_macro2::TokenStream {
-    // Generate CTypeInfo array for args
-    // Fast API signature: receiver (V8Value) + user args
-    let receiver_ctype = quote!(v8::fast_api::Type::V8Value.as_info());
-    let arg_ctypes: Vec<_> = fast_param_types.iter().map(|t| t.quote_ctype()).collect();
-    let return_ctype = fast_return.quote_ctype();
-
-    // Generate fast function parameters
-    let fast_params: Vec<_> = params
-        .iter()
-        .enumerate()
-        .map(|(idx, (name, _))| {
-            let rust_type = fast_param_types[idx].quote_rust_type();
-            quote!(#name: #rust_type)
-        })
-        .collect();
-
-    let fast_return_rust = fast_return.quote_rust_type();
+    let fast_doc = quote! {
+        /// V8 Fast API callback - auto-generated by glue_v8::method(fast)
+        ///
+        /// This is called directly by V8's optimizing compiler for hot paths.
+        /// ~10x faster than the slow path for primitive-only functions.
+    };
+    let cfunction_names = fast_cfunction_names(fn_name, params);
+    let fast_fns = generate_fast_overloads(
+        fn_name,
+        params,
+        fast_param_types,
+        fast_return,
+        &quote!(#fn_name),
+        call_args,
+        &input_fn.sig.output,
+        int64,
+        &fast_doc,
+        &quote!(),
+        &quote!(),
+        counters,
+    );
 
-    // Arguments to pass to original function
-    let call_args_for_fast: Vec<_> = params.iter().map(|(name, _)| quote!(#name)).collect();
+    let length = length as i32;
+    let naming = generate_template_naming(js_name);
+    let raw_name = raw_callback_name(wrapper_name);
+    let external_refs = generate_external_refs(fn_name, wrapper_name, &cfunction_names);
+    let install_fn = generate_install_fn(fn_name, js_name, None, &quote!(#template_fn_name(scope)));
+    let count_slow = counters.count_slow();
+    let counter_statics = counters.statics();
 
     quote! {
         #input_fn
 
         /// V8 callback wrapper (slow path) - auto-generated by glue_v8::method
         pub fn #wrapper_name(
-            scope: &mut v8::PinScope,
-            args: v8::FunctionCallbackArguments,
-            mut rv: v8::ReturnValue,
+            __v8g_scope: &mut v8::PinScope,
+            __v8g_args: v8::FunctionCallbackArguments,
+            mut __v8g_rv: v8::ReturnValue,
         ) {
+            #count_slow
             #state_extraction
             #(#arg_extractions)*
             #call_and_return
         }
 
-        /// V8 Fast API callback - auto-generated by glue_v8::method(fast)
-        ///
-        /// This is called directly by V8's optimizing compiler for hot paths.
-        /// ~10x faster than the slow path for primitive-only functions.
-        extern "C" fn #fast_fn_name(
-            _recv: v8::Local<v8::Value>,
-            #(#fast_params,)*
-            _options: *mut v8::fast_api::FastApiCallbackOptions,
-        ) -> #fast_return_rust {
-            // Call the original function directly
-            #fn_name(#(#call_args_for_fast),*)
-        }
-
-        /// CFunctionInfo for the fast call signature
-        const #cfunction_info_name: v8::fast_api::CFunctionInfo = v8::fast_api::CFunctionInfo::new(
-            #return_ctype,
-            &[#receiver_ctype, #(#arg_ctypes),*],
-            v8::fast_api::Int64Representation::BigInt,
-        );
-
-        /// CFunction definition for V8 Fast API
-        pub const #cfunction_name: v8::fast_api::CFunction = v8::fast_api::CFunction::new(
-            #fast_fn_name as *const std::ffi::c_void,
-            &#cfunction_info_name,
-        );
+        #fast_fns
 
         /// Create a FunctionTemplate with both slow and fast paths
         ///
@@ -318,17 +875,24 @@ fn generate_fast_api_pure(
         /// # Example
         ///
         /// ```ignore
-        /// let template = add_v8_template(scope, None);
+        /// let template = add_v8_template(scope);
         /// let func = template.get_function(scope).unwrap();
         /// ```
         pub fn #template_fn_name<'s>(
             scope: &mut v8::PinScope<'s, '_>,
-            data: Option<v8::Local<'s, v8::Value>>,
         ) -> v8::Local<'s, v8::FunctionTemplate> {
-            v8::FunctionTemplate::builder(#wrapper_name)
-                .data(data.unwrap_or_else(|| v8::undefined(scope).into()))
-                .build_fast(scope, &[#cfunction_name])
+            let template = v8::FunctionTemplate::builder_raw(#raw_name)
+                .length(#length)
+                .build_fast(scope, &[#(#cfunction_names),*]);
+            #naming
+            template
         }
+
+        #install_fn
+
+        #external_refs
+
+        #counter_statics
     }
 }
 
@@ -342,141 +906,216 @@ fn generate_fast_api_with_state(
     input_fn: &ItemFn,
     fn_name: &syn::Ident,
     wrapper_name: &syn::Ident,
-    fast_fn_name: &syn::Ident,
     template_fn_name: &syn::Ident,
-    cfunction_name: &syn::Ident,
-    cfunction_info_name: &syn::Ident,
     params: &[(syn::Ident, Box<Type>)],
     fast_param_types: &[FastApiType],
     fast_return: FastApiType,
     state_type: &Type,
+    state_extraction: &proc_macro2::TokenStream,
     arg_extractions: &[proc_macro2::TokenStream],
+    call_args: &[proc_macro2::TokenStream],
     call_and_return: &proc_macro2::TokenStream,
+    js_name: &str,
+    length: usize,
+    state_mode: StateMode,
+    state_mut: bool,
+    int64: Int64Repr,
+    counters: &CallCounters,
 ) -> proc_macro2::TokenStream {
-    // Generate CTypeInfo array for args
-    // Fast API signature: receiver (V8Value) + user args + CallbackOptions
-    let receiver_ctype = quote!(v8::fast_api::Type::V8Value.as_info());
-    let arg_ctypes: Vec<_> = fast_param_types.iter().map(|t| t.quote_ctype()).collect();
-    let options_ctype = quote!(v8::fast_api::Type::CallbackOptions.as_info());
-    let return_ctype = fast_return.quote_ctype();
-
-    // Generate fast function parameters
-    let fast_params: Vec<_> = params
-        .iter()
-        .enumerate()
-        .map(|(idx, (name, _))| {
-            let rust_type = fast_param_types[idx].quote_rust_type();
-            quote!(#name: #rust_type)
-        })
-        .collect();
+    let kind = StateKind::of(state_type);
+    let inner_state_type = kind.inner();
+    let state_borrow = state_mut.then(|| generate_state_borrow_mut(true));
 
-    let fast_return_rust = fast_return.quote_rust_type();
+    let template_length = length as i32;
+    let naming = generate_template_naming(js_name);
+    let raw_name = raw_callback_name(wrapper_name);
+    let cfunction_names = fast_cfunction_names(fn_name, params);
+    let external_refs = generate_external_refs(fn_name, wrapper_name, &cfunction_names);
 
-    // Arguments to pass to original function (with state)
-    let call_args_for_fast: Vec<_> = params.iter().map(|(name, _)| quote!(#name)).collect();
+    // Fast path state resolution, template and install helpers depend on where state lives
+    let (fast_state_resolution, template_fn, install_fn) = if state_mode.is_slot() {
+        let isolate_slot = state_slot(kind).filter(|_| state_mode == StateMode::IsolateSlot);
+        let context_slot =
+            context_state_slot(kind).filter(|_| state_mode == StateMode::ContextSlot);
+        let fast_state_resolution = if let Some((slot_ty, resolve)) = isolate_slot {
+            let not_bound = state_not_bound_message("an isolate", &slot_ty);
+            quote! {
+                // SAFETY: options is valid during fast call, and carries the current isolate
+                let __v8g_options = unsafe { &*__v8g_options };
+                let __v8g_state = match unsafe { __v8g_options.isolate_unchecked() }.get_slot::<#slot_ty>() {
+                    Some(state) => #resolve,
+                    None => {
+                        let scope = std::pin::pin!(unsafe { v8::CallbackScope::new(__v8g_options) });
+                        let scope = scope.init();
+                        let msg = v8::String::new(&scope, #not_bound).unwrap();
+                        let err = v8::Exception::error(&scope, msg);
+                        scope.throw_exception(err);
+                        return Default::default();
+                    }
+                };
+            }
+        } else if let Some((key_ty, resolve)) = context_slot {
+            let not_bound = state_not_bound_message("a context", &quote!(std::rc::Rc<#key_ty>));
+            quote! {
+                // SAFETY: options is valid during fast call; its scope gives the current context
+                let __v8g_options = unsafe { &*__v8g_options };
+                let __v8g_state = {
+                    let scope = std::pin::pin!(unsafe { v8::CallbackScope::new(__v8g_options) });
+                    let scope = scope.init();
+                    match scope.get_current_context().get_slot::<#key_ty>() {
+                        Some(state) => #resolve,
+                        None => {
+                            let msg = v8::String::new(&scope, #not_bound).unwrap();
+                            let err = v8::Exception::error(&scope, msg);
+                            scope.throw_exception(err);
+                            return Default::default();
+                        }
+                    }
+                };
+            }
+        } else {
+            // The slow path already reports the unsupported state type
+            quote! {
+                let _ = __v8g_options;
+                return Default::default();
+            }
+        };
+        let slot_doc = if state_mode == StateMode::IsolateSlot {
+            quote! {
+                /// Snapshot-safe: the template carries no data, and both paths resolve the
+                /// state from the isolate slot of the state type. Bind it with
+                /// `isolate.set_slot(state)`, again after restoring a snapshot.
+                ///
+                /// # Example
+                ///
+                /// ```ignore
+                /// isolate.set_slot(Rc::new(MyState { ... }));
+            }
+        } else {
+            quote! {
+                /// The template carries no data, and both paths resolve the state from a
+                /// slot of the current context. Bind it in each context with
+                /// `context.set_slot(state)`.
+                ///
+                /// # Example
+                ///
+                /// ```ignore
+                /// context.set_slot(Rc::new(MyState { ... }));
+            }
+        };
+        let template_fn = quote! {
+            /// Create a FunctionTemplate with both slow and fast paths
+            ///
+            #slot_doc
+            /// let template = my_fn_v8_template(scope);
+            /// let func = template.get_function(scope).unwrap();
+            /// ```
+            pub fn #template_fn_name<'s>(
+                scope: &mut v8::PinScope<'s, '_>,
+            ) -> v8::Local<'s, v8::FunctionTemplate> {
+                let template = v8::FunctionTemplate::builder_raw(#raw_name)
+                    .length(#template_length)
+                    .build_fast(scope, &[#(#cfunction_names),*]);
+                #naming
+                template
+            }
+        };
+        let install_fn =
+            generate_install_fn(fn_name, js_name, None, &quote!(#template_fn_name(scope)));
 
-    // Determine the inner type for state (unwrap Rc if present)
-    let inner_state_type = if let Some(inner) = get_rc_inner_type(state_type) {
-        inner.clone()
+        (fast_state_resolution, template_fn, install_fn)
     } else {
-        state_type.clone()
+        // Wrap counted pointers without incrementing the refcount
+        let state = match kind {
+            StateKind::Rc(_) => quote! {
+                std::mem::ManuallyDrop::new(std::rc::Rc::from_raw(ptr))
+            },
+            StateKind::Arc(_) => quote! {
+                std::mem::ManuallyDrop::new(std::sync::Arc::from_raw(ptr))
+            },
+            StateKind::Static(_) | StateKind::Value(_) => quote!(&*ptr),
+        };
+        let fast_state_resolution = quote! {
+            // SAFETY: options is valid during fast call, data was set to External
+            let __v8g_state = unsafe {
+                let __v8g_options = &*__v8g_options;
+                let external = v8::Local::<v8::External>::cast_unchecked(__v8g_options.data);
+                let ptr = external.value() as *const #inner_state_type;
+                #state
+            };
+        };
+        let template_fn = generate_state_template(
+            fn_name,
+            wrapper_name,
+            state_type,
+            state_mode,
+            js_name,
+            length,
+            &cfunction_names,
+        );
+        let install_fn = generate_install_fn(
+            fn_name,
+            js_name,
+            Some((state_type, state_mode)),
+            &quote!(#template_fn_name(scope, state)),
+        );
+
+        (fast_state_resolution, template_fn, install_fn)
     };
 
-    let state_ty_str = quote!(#state_type).to_string();
+    let fast_doc = quote! {
+        /// V8 Fast API callback - auto-generated by glue_v8::method(fast, state)
+        ///
+        /// This is called directly by V8's optimizing compiler for hot paths.
+        /// State is extracted from FastApiCallbackOptions.data (or the isolate slot).
+    };
+    let fast_fns = generate_fast_overloads(
+        fn_name,
+        params,
+        fast_param_types,
+        fast_return,
+        &quote!(#fn_name),
+        call_args,
+        &input_fn.sig.output,
+        int64,
+        &fast_doc,
+        &quote! {
+            #fast_state_resolution
+            #state_borrow
+        },
+        &quote!(),
+        counters,
+    );
+    let count_slow = counters.count_slow();
+    let counter_statics = counters.statics();
 
     quote! {
         #input_fn
 
         /// V8 callback wrapper (slow path) - auto-generated by glue_v8::method(fast, state)
         ///
-        /// State is extracted from function data (External), NOT context slots.
+        /// State is extracted from function data (External), or from the isolate slot
+        /// for snapshot-safe state.
         pub fn #wrapper_name(
-            scope: &mut v8::PinScope,
-            args: v8::FunctionCallbackArguments,
-            mut rv: v8::ReturnValue,
+            __v8g_scope: &mut v8::PinScope,
+            __v8g_args: v8::FunctionCallbackArguments,
+            mut __v8g_rv: v8::ReturnValue,
         ) {
-            // Extract state from function data (External containing Rc<State>)
-            let state: #state_type = unsafe {
-                let data = args.data();
-
-                if data.is_undefined() || data.is_null() {
-                    let msg = v8::String::new(scope, concat!("internal error: state data not set for ", #state_ty_str)).unwrap();
-                    let err = v8::Exception::error(scope, msg);
-                    scope.throw_exception(err);
-                    return;
-                }
-
-                let external = v8::Local::<v8::External>::try_from(data).unwrap();
-                let ptr = external.value() as *const #inner_state_type;
-                std::rc::Rc::clone(&*std::mem::ManuallyDrop::new(std::rc::Rc::from_raw(ptr)))
-            };
+            #count_slow
+            #state_extraction
 
             #(#arg_extractions)*
             #call_and_return
         }
 
-        /// V8 Fast API callback - auto-generated by glue_v8::method(fast, state)
-        ///
-        /// This is called directly by V8's optimizing compiler for hot paths.
-        /// State is extracted from FastApiCallbackOptions.data.
-        extern "C" fn #fast_fn_name<'s>(
-            _recv: v8::Local<v8::Value>,
-            #(#fast_params,)*
-            options: *mut v8::fast_api::FastApiCallbackOptions<'s>,
-        ) -> #fast_return_rust {
-            // SAFETY: options is valid during fast call, data was set to External
-            let state: &#inner_state_type = unsafe {
-                let options = &*options;
-                let external = v8::Local::<v8::External>::cast_unchecked(options.data);
-                &*(external.value() as *const #inner_state_type)
-            };
-
-            // Wrap in Rc for the function call (without incrementing refcount)
-            let state: std::mem::ManuallyDrop<std::rc::Rc<#inner_state_type>> = unsafe {
-                std::mem::ManuallyDrop::new(std::rc::Rc::from_raw(state))
-            };
+        #fast_fns
 
-            #fn_name(&state, #(#call_args_for_fast),*)
-        }
+        #template_fn
 
-        /// CFunctionInfo for the fast call signature (with CallbackOptions for state)
-        const #cfunction_info_name: v8::fast_api::CFunctionInfo = v8::fast_api::CFunctionInfo::new(
-            #return_ctype,
-            &[#receiver_ctype, #(#arg_ctypes,)* #options_ctype],
-            v8::fast_api::Int64Representation::BigInt,
-        );
+        #install_fn
 
-        /// CFunction definition for V8 Fast API
-        pub const #cfunction_name: v8::fast_api::CFunction = v8::fast_api::CFunction::new(
-            #fast_fn_name as *const std::ffi::c_void,
-            &#cfunction_info_name,
-        );
+        #external_refs
 
-        /// Create a FunctionTemplate with both slow and fast paths
-        ///
-        /// IMPORTANT: State is passed via External, NOT context slots.
-        /// The Rc is NOT cloned - the caller must ensure the state outlives the function.
-        ///
-        /// # Example
-        ///
-        /// ```ignore
-        /// let state = Rc::new(MyState { ... });
-        /// let template = my_fn_v8_template(scope, &state);
-        /// let func = template.get_function(scope).unwrap();
-        /// ```
-        pub fn #template_fn_name<'s>(
-            scope: &mut v8::PinScope<'s, '_>,
-            state: &std::rc::Rc<#inner_state_type>,
-        ) -> v8::Local<'s, v8::FunctionTemplate> {
-            // Create External containing raw pointer to inner state
-            // SAFETY: The Rc ensures the state lives long enough, and we use ManuallyDrop
-            // in both paths to avoid double-free
-            let ptr = std::rc::Rc::as_ptr(state);
-            let external = v8::External::new(scope, ptr as *mut std::ffi::c_void);
-
-            v8::FunctionTemplate::builder(#wrapper_name)
-                .data(external.into())
-                .build_fast(scope, &[#cfunction_name])
-        }
+        #counter_statics
     }
 }
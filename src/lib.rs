@@ -31,31 +31,112 @@
 //! }
 //!
 //! // Registration:
-//! let template = add_v8_template(scope, None);
+//! let template = add_v8_template(scope);
 //! let func = template.get_function(scope).unwrap();
 //! ```
 //!
 //! Requirements for Fast API:
-//! - Only primitive types: bool, i32, u32, i64, u64, f32, f64
+//! - Only primitive types: bool, i32, u32, i64, u64, f32, f64, and `&str` params
+//!   (one-byte strings; other strings take the slow path)
+//! - Trailing `Option`s of a number or bool get one fast overload per arity,
+//!   with `None` for the omitted arguments
 //! - No scope parameter (cannot use V8 APIs in fast path)
-//! - Return type must be a primitive or void
+//! - Return type must be a primitive or void, or a `Result` of one (`Err` throws
+//!   the same `Error` as on the slow path)
+//!
+//! Otherwise the op silently uses the slow path only; `fast(require)` turns
+//! that fallback into a compile error naming the blocking parameter or type.
+//!
+//! V8 also stays on the slow path, without telling, when it rejects the
+//! registered signature. With the `fast-counters` cargo feature, every `fast`
+//! op counts its calls in `{FN}_V8_SLOW_CALLS` and `{FN}_V8_FAST_CALLS`
+//! (`{TYPE}_{METHOD}_V8_..._CALLS` for class methods) `AtomicUsize` statics, so
+//! tests can check that optimized code takes the fast path. Overloads shorter
+//! than the full arity count in `{FN}_V8_FAST_CALLS_{arity}`.
+//!
+//! The feature is read when the macro expands, and cargo builds glue_v8 once
+//! per build with the union of the features requested: a single dependent
+//! enabling `fast-counters` adds the counters (and their atomic increments) to
+//! the ops of every crate in the build. Enable it from test-only builds.
+//!
+//! ## Classes
+//!
+//! `#[glue_v8::class]` on a struct and `#[glue_v8::methods]` on its impl block
+//! expose the struct as a JS class. The Rust value lives in an internal field
+//! of each instance and is dropped when the object is garbage collected (or
+//! when the isolate is disposed).
+//!
+//! ```ignore
+//! #[glue_v8::class]
+//! struct Counter {
+//!     value: i32,
+//! }
+//!
+//! #[glue_v8::methods]
+//! impl Counter {
+//!     #[constructor]
+//!     fn new(start: i32) -> Self {
+//!         Counter { value: start }
+//!     }
+//!
+//!     fn increment(&mut self, amount: i32) -> i32 {
+//!         self.value += amount;
+//!         self.value
+//!     }
+//! }
+//!
+//! // Registration:
+//! let class = Counter_v8_class_template(scope).get_function(scope).unwrap();
+//! ```
+//!
+//! ## Accessors
+//!
+//! `#[glue_v8::getter]` and `#[glue_v8::setter]` generate accessor callbacks
+//! for native properties (`navigator.userAgent`, `request.url`, ...).
+//!
+//! ```ignore
+//! #[glue_v8::getter(state = Rc<RequestState>, setter = set_url)]
+//! fn url(state: &Rc<RequestState>) -> String {
+//!     state.url.borrow().clone()
+//! }
+//!
+//! #[glue_v8::setter(state = Rc<RequestState>)]
+//! fn set_url(state: &Rc<RequestState>, value: String) {
+//!     *state.url.borrow_mut() = value;
+//! }
+//!
+//! // Registration (getter and setter):
+//! let config = url_v8_accessor(scope, &state);
+//! template.set_accessor_with_configuration(key.into(), config);
+//! ```
 
+mod accessor;
+mod async_op;
+mod class;
 mod codegen;
+mod dts;
 mod fast;
 mod parse;
+mod snapshot;
 mod types;
 
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{FnArg, ItemFn, Pat, ReturnType, parse_macro_input};
+use syn::{ItemFn, ReturnType, parse_macro_input};
 
+use async_op::generate_async_call;
 use codegen::{
-    generate_arg_extractions, generate_call_and_return, generate_state_extraction,
-    generate_state_template,
+    generate_arg_extractions, generate_call_and_return, generate_call_args, generate_install_fn,
+    generate_plain_template_fn, generate_registry_state_extractions, generate_state_extraction,
+    generate_state_template, return_conversion, state_call_arg,
 };
+use dts::generate_dts_const;
 use fast::generate_fast_api_code;
-use parse::MethodAttrs;
-use types::is_result_type;
+use parse::{ClassAttrs, MethodAttrs, OpParams, ParamRole, StateMode, strip_param_markers};
+use snapshot::{generate_external_refs, generate_raw_callback};
+use types::{
+    StateKind, get_registry_state_type, infer_state_type, is_result_type, required_param_count,
+};
 
 /// Generate a V8 callback wrapper for a Rust function.
 ///
@@ -81,7 +162,7 @@ use types::is_result_type;
 /// let func = schedule_timeout_v8_template(scope, &state).get_function(scope).unwrap();
 /// ```
 ///
-/// With custom JS name:
+/// With custom JS name (a JS identifier, not a reserved word):
 /// ```ignore
 /// #[glue_v8::method(name = "setTimeout")]
 /// fn set_timeout(scope: &mut v8::PinScope, delay: f64) -> u64 { ... }
@@ -105,6 +186,35 @@ use types::is_result_type;
 /// }
 /// ```
 ///
+/// With async fn (real asynchronous op, returns a pending JS Promise):
+/// ```ignore
+/// // The spawner trait is the embedder's (glue_v8 has no runtime crate)
+/// trait Spawner {
+///     fn spawn(future: Pin<Box<dyn Future<Output = ()>>>);
+/// }
+///
+/// struct LocalSpawner;
+///
+/// impl Spawner for LocalSpawner {
+///     fn spawn(future: Pin<Box<dyn Future<Output = ()>>>) {
+///         tokio::task::spawn_local(future);
+///     }
+/// }
+///
+/// #[glue_v8::method(promise, spawn = LocalSpawner)]
+/// async fn fetch_text(url: String) -> Result<String, String> {
+///     let response = client().get(&url).send().await.map_err(|e| e.to_string())?;
+///     response.text().await.map_err(|e| e.to_string())
+/// }
+///
+/// // Event loop turn, after polling the executor (settles every async op):
+/// glue_v8::drain!(scope);
+/// ```
+///
+/// The future outlives the call, so async ops take state as `Rc<T>`/`Arc<T>`
+/// (a clone, by value) or `&'static T`; plain `T` state, `&Rc<T>` params and
+/// borrowed `#[state]` params are compile errors.
+///
 /// With optional parameters:
 /// ```ignore
 /// #[glue_v8::method]
@@ -126,79 +236,254 @@ use types::is_result_type;
 /// }
 ///
 /// // With state - state is passed via FunctionTemplate data
-/// #[glue_v8::method(fast, state = Rc<TimerState>)]
+/// #[glue_v8::method(fast, state = Rc<TimerState>, int64 = "number")]
 /// fn schedule_timeout(state: &Rc<TimerState>, id: u64, delay: u64) {
 ///     let _ = state.scheduler_tx.send(SchedulerMessage::ScheduleTimeout(id, delay));
 /// }
 /// ```
 ///
+/// `i64`/`u64` params and return values convert via serde_v8 by default
+/// (Numbers are truncated, BigInts accepted). `int64 = "number"` makes them
+/// Numbers that throw a RangeError beyond `Number.MAX_SAFE_INTEGER`, and
+/// `int64 = "bigint"` BigInts. Both the slow and fast paths (and the TypeScript
+/// declaration) follow it, and `fast` ops with `i64`/`u64` must set it:
+/// ```ignore
+/// #[glue_v8::method(fast, int64 = "bigint")]
+/// fn hrtime_ns() -> u64 { ... }
+/// ```
+///
+/// Parameters are classified by type, in any order: `&mut v8::PinScope` is the
+/// scope, `&v8::Isolate` the isolate, the declared state type (or a param named
+/// `state`) the state, and the rest are JS arguments. `#[scope]` and `#[isolate]`
+/// mark them explicitly (e.g. behind a type alias):
+/// ```ignore
+/// #[glue_v8::method(state = Rc<Counter>)]
+/// fn add_scaled(amount: i32, counter: &Rc<Counter>, #[scope] cx: &mut Scope, factor: i32) -> i32 { ... }
+/// ```
+///
+/// Owned state (the template holds a strong Rc, released after garbage collection
+/// or on isolate disposal):
+/// ```ignore
+/// #[glue_v8::method(state = Rc<Cache>, owned)]
+/// fn cache_get(state: &Rc<Cache>, key: String) -> Option<String> { ... }
+///
+/// let template = cache_get_v8_template(scope, Rc::new(Cache::default()));
+/// ```
+///
+/// `Arc<T>` and `&'static T` state work like `Rc<T>`; plain `T` is moved into
+/// (and owned by) the template, and the op receives `&T`:
+/// ```ignore
+/// #[glue_v8::method(state = Arc<Config>)]
+/// fn max_body_size(state: &Arc<Config>) -> u32 { state.max_body_size }
+///
+/// #[glue_v8::method(state = Limits)]
+/// fn max_depth(state: &Limits) -> u32 { state.max_depth }
+///
+/// let template = max_depth_v8_template(scope, Limits::default());
+/// ```
+///
+/// Mutable state: take `state: &mut T` with a `RefCell` state type. The state is
+/// borrowed for the call; re-entering while it is borrowed throws an `Error`:
+/// ```ignore
+/// #[glue_v8::method(state = Rc<RefCell<Journal>>)]
+/// fn journal_append(state: &mut Journal, entry: String) { state.entries.push(entry); }
+/// ```
+///
+/// Several states: mark parameters with `#[state]`. Each `&T` is resolved from
+/// the context slot of type `T`, then the isolate slot of type `Rc<T>`; a missing
+/// type throws an `Error`:
+/// ```ignore
+/// #[glue_v8::method]
+/// fn set_timeout(#[state] timers: &TimerState, #[state] perms: &Permissions, delay: u64) { ... }
+///
+/// isolate.set_slot(Rc::new(Permissions::default()));
+/// context.set_slot(Rc::new(TimerState::default()));
+/// ```
+///
+/// State from a context slot (per realm), registered with `v8::Function::new`
+/// (the state type comes from the `state` param unless given with `= Type`):
+/// ```ignore
+/// #[glue_v8::method(state(slot = context))]
+/// fn realm_name(state: &Rc<RealmInfo>) -> String { state.name.clone() }
+///
+/// context.set_slot(Rc::new(RealmInfo { ... }));
+/// let func = v8::Function::new(scope, realm_name_v8).unwrap();
+/// ```
+///
+/// Snapshot-safe state (`snapshot` or `state(slot = isolate)`, resolved from the
+/// isolate slot of the state type, e.g. `Rc<T>`):
+/// ```ignore
+/// #[glue_v8::method(state = Rc<TimerState>, snapshot)]
+/// fn clear_timeout(state: &Rc<TimerState>, id: u64) { ... }
+///
+/// // After creating the isolate, or restoring it from a snapshot:
+/// isolate.set_slot(Rc::new(TimerState { ... }));
+/// ```
+///
 /// Note: Fast API functions generate both slow and fast paths.
-/// Use `{fn_name}_v8_template(scope, state_external)` to register with FunctionTemplate.
+/// Every op has a `{fn_name}_v8_template(scope)` helper (`(scope, state)` with
+/// template state) to register with FunctionTemplate.
 #[proc_macro_attribute]
 pub fn method(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let attrs = MethodAttrs::parse(attr);
-    let input_fn = parse_macro_input!(item as ItemFn);
+    let mut attrs = match MethodAttrs::parse(attr) {
+        Ok(attrs) => attrs,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let mut input_fn = parse_macro_input!(item as ItemFn);
+
+    // Extract parameters, tracking their roles (scope, isolate, state, #[state], JS args)
+    let op = match OpParams::from_signature(&input_fn.sig, Some(&attrs)) {
+        Ok(op) => op,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    strip_param_markers(&mut input_fn.sig);
+    let OpParams {
+        params,
+        has_scope,
+        has_isolate,
+        has_state,
+        state_mut,
+        states,
+        ..
+    } = &op;
+    let (has_scope, has_isolate, has_state, state_mut) =
+        (*has_scope, *has_isolate, *has_state, *state_mut);
+
+    // `state(slot = ..)` without `= Type`: the state type comes from the `state` parameter
+    if attrs.state_type.is_none()
+        && attrs.state_mode.is_slot()
+        && let Some(param_ty) = &op.state_param
+    {
+        attrs.state_type = infer_state_type(param_ty);
+    }
+
+    if has_scope && has_isolate {
+        return syn::Error::new_spanned(
+            &input_fn.sig.inputs,
+            "ops take either the scope or the isolate, not both (the isolate is borrowed from the scope)",
+        )
+        .to_compile_error()
+        .into();
+    }
+
+    // The JS name is declared as a function in the TypeScript declarations
+    if let Some(js_name) = &attrs.js_name
+        && dts::JS_RESERVED_WORDS.contains(&js_name.as_str())
+    {
+        let span = attrs
+            .keys
+            .iter()
+            .find(|(key, _)| key == "name")
+            .map_or_else(proc_macro2::Span::call_site, |(_, span)| *span);
+        return syn::Error::new(
+            span,
+            format!("`{}` is a reserved word in JavaScript", js_name),
+        )
+        .to_compile_error()
+        .into();
+    }
+
     let fn_name = &input_fn.sig.ident;
-    let _js_name = attrs.js_name.unwrap_or_else(|| fn_name.to_string());
+    let js_name = attrs.js_name.clone().unwrap_or_else(|| fn_name.to_string());
     let wrapper_name = syn::Ident::new(&format!("{}_v8", fn_name), fn_name.span());
 
-    // Extract parameters, tracking which are special (scope, state)
-    let mut has_scope = false;
-    let mut has_state = false;
-    let params: Vec<_> = input_fn
-        .sig
-        .inputs
-        .iter()
-        .filter_map(|arg| {
-            if let FnArg::Typed(pat_type) = arg {
-                if let Pat::Ident(pat_ident) = &*pat_type.pat {
-                    let name = &pat_ident.ident;
-                    let ty = &pat_type.ty;
-
-                    // Skip 'scope' or '_scope' - provided by V8 callback
-                    let name_str = name.to_string();
-                    if name_str == "scope" || name_str == "_scope" {
-                        has_scope = true;
-                        return None;
-                    }
-
-                    // Skip 'state' - will be extracted from FunctionTemplate data
-                    if name_str == "state" {
-                        has_state = true;
-                        return None;
-                    }
-
-                    return Some((name.clone(), ty.clone()));
-                }
-            }
-            None
-        })
-        .collect();
-
     // Generate argument extraction code
-    let arg_extractions = generate_arg_extractions(&params);
+    let arg_extractions = generate_arg_extractions(params, attrs.int64);
 
-    // Generate state extraction if needed
-    let state_extraction = generate_state_extraction(has_state, &attrs.state_type);
+    // Generate state extraction if needed, then resolve `#[state]` params from the registry
+    let state_extraction =
+        match generate_state_extraction(&op, attrs.state_type.as_ref(), attrs.state_mode) {
+            Ok(extraction) => extraction,
+            Err(err) => return err.to_compile_error().into(),
+        };
+    let (registry_extractions, registry_call_args) =
+        match generate_registry_state_extractions(states) {
+            Ok(v) => v,
+            Err(err) => return err.to_compile_error().into(),
+        };
+    let state_extraction = quote! {
+        #state_extraction
+        #(#registry_extractions)*
+    };
 
-    // Generate function call arguments
-    let call_args: Vec<_> = {
-        let mut args = Vec::new();
+    let is_async = input_fn.sig.asyncness.is_some();
 
-        if has_scope {
-            args.push(quote! { scope });
-        }
+    if is_async {
+        let error = if attrs.spawn.is_none() {
+            Some("async ops need a spawner: #[glue_v8::method(promise, spawn = SpawnerType)]")
+        } else if attrs.fast {
+            Some("async ops cannot use the Fast API")
+        } else if has_scope || has_isolate {
+            Some(
+                "async ops cannot take the scope or isolate (they do not live across await points)",
+            )
+        } else if state_mut {
+            Some(
+                "async ops cannot take `&mut` state (the borrow does not live across await points)",
+            )
+        } else {
+            None
+        };
 
-        if has_state {
-            args.push(quote! { &state });
+        if let Some(msg) = error {
+            return syn::Error::new_spanned(input_fn.sig.asyncness, msg)
+                .to_compile_error()
+                .into();
         }
 
-        for (name, _) in &params {
-            args.push(quote! { #name });
+        // The future outlives the call, so it only takes owned or `'static` state
+        if let (Some(state_ty), Some(state_param)) = (&attrs.state_type, &op.state_param)
+            && has_state
+            && matches!(StateKind::of(state_ty), StateKind::Value(_))
+        {
+            return syn::Error::new_spanned(
+                state_param,
+                "async ops cannot borrow plain `T` state (the template may release it while the future is pending); use `Rc<T>`, `Arc<T>` or `&'static T`",
+            )
+            .to_compile_error()
+            .into();
+        }
+        // The call passes a clone of `Rc<T>`/`Arc<T>` state, which outlives the call
+        if let (Some(state_ty), Some(state_param)) = (&attrs.state_type, &op.state_param)
+            && has_state
+            && matches!(
+                StateKind::of(state_ty),
+                StateKind::Rc(_) | StateKind::Arc(_)
+            )
+            && matches!(&**state_param, syn::Type::Reference(_))
+        {
+            return syn::Error::new_spanned(
+                state_param,
+                "async ops take `Rc<T>`/`Arc<T>` state by value (the future owns a clone), not by reference",
+            )
+            .to_compile_error()
+            .into();
+        }
+        if let Some((_, ty)) = states
+            .iter()
+            .find(|(_, ty)| matches!(get_registry_state_type(ty), Some((_, false))))
+        {
+            return syn::Error::new_spanned(
+                ty,
+                "async ops take `#[state]` params as `Rc<T>` (a borrow does not live across await points)",
+            )
+            .to_compile_error()
+            .into();
         }
+    } else if let Some(spawn) = &attrs.spawn {
+        return syn::Error::new_spanned(spawn, "`spawn` only applies to `async fn` ops")
+            .to_compile_error()
+            .into();
+    }
+    if let Some(setter) = &attrs.setter {
+        return syn::Error::new_spanned(setter, "`setter` only applies to glue_v8::getter")
+            .to_compile_error()
+            .into();
+    }
 
-        args
-    };
+    // Generate function call arguments, in declaration order
+    let state_arg = state_call_arg(attrs.state_type.as_ref(), is_async, state_mut);
+    let call_args = generate_call_args(&op, &state_arg, &registry_call_args);
 
     // Check if function has a return type and if it's a Result
     let has_return = !matches!(input_fn.sig.output, ReturnType::Default);
@@ -208,17 +493,38 @@ pub fn method(attr: TokenStream, item: TokenStream) -> TokenStream {
         false
     };
 
-    let call_and_return = generate_call_and_return(
-        fn_name,
-        &call_args,
-        has_return,
-        returns_result,
-        attrs.promise,
-    );
+    let call_and_return = match &attrs.spawn {
+        Some(spawn) if is_async => generate_async_call(
+            fn_name,
+            &call_args,
+            spawn,
+            &input_fn.sig.output,
+            has_return,
+            returns_result,
+            attrs.int64,
+            &js_name,
+        ),
+        _ => generate_call_and_return(
+            &quote!(#fn_name),
+            &call_args,
+            has_return,
+            returns_result,
+            attrs.promise,
+            return_conversion(&input_fn.sig.output, attrs.int64),
+            &js_name,
+        ),
+    };
 
-    // Generate template function name for stateful functions
+    // `{fn}_v8_template` is emitted in every mode
     let template_fn_name = syn::Ident::new(&format!("{}_v8_template", fn_name), fn_name.span());
 
+    // `length` of the JS function: trailing Option params are optional
+    let length = required_param_count(params);
+
+    // Snapshot support: raw callback used by the templates, and its external references
+    let raw_callback = generate_raw_callback(&wrapper_name, &quote!(#wrapper_name));
+    let external_refs = generate_external_refs(fn_name, &wrapper_name, &[]);
+
     // Generate the expanded code
     let expanded = if attrs.fast {
         // Fast API mode: generate both slow and fast paths
@@ -226,30 +532,66 @@ pub fn method(attr: TokenStream, item: TokenStream) -> TokenStream {
             &input_fn,
             fn_name,
             &wrapper_name,
-            &params,
-            has_scope,
+            params,
+            // The scope, isolate and `#[state]` params need the slow path
+            op.roles
+                .iter()
+                .find(|(_, role)| {
+                    matches!(
+                        role,
+                        ParamRole::Scope | ParamRole::Isolate | ParamRole::RegistryState
+                    )
+                })
+                .map(|(name, _)| name),
+            attrs.promise,
+            attrs.fast_required,
             has_state,
             &attrs.state_type,
             &state_extraction,
             &arg_extractions,
+            &call_args,
             &call_and_return,
+            &js_name,
+            length,
+            attrs.state_mode,
+            state_mut,
+            attrs.int64,
         )
-    } else if has_state {
+    } else if let Some(state_type) = attrs.state_type.as_ref().filter(|_| has_state) {
         // Non-fast with state: generate wrapper + template function
-        let state_type = attrs
-            .state_type
-            .as_ref()
-            .expect("Function has 'state' parameter but no state type specified");
-        let template_fn = generate_state_template(&wrapper_name, &template_fn_name, state_type);
+        let (template_fn, install_fn) = match attrs.state_mode {
+            StateMode::Borrowed | StateMode::Owned => (
+                generate_state_template(
+                    fn_name,
+                    &wrapper_name,
+                    state_type,
+                    attrs.state_mode,
+                    &js_name,
+                    length,
+                    &[],
+                ),
+                generate_install_fn(
+                    fn_name,
+                    &js_name,
+                    Some((state_type, attrs.state_mode)),
+                    &quote!(#template_fn_name(scope, state)),
+                ),
+            ),
+            // Slot state lives in the isolate or context, so the template carries no data
+            StateMode::IsolateSlot | StateMode::ContextSlot => (
+                generate_plain_template_fn(&wrapper_name, &template_fn_name, &js_name, length),
+                generate_install_fn(fn_name, &js_name, None, &quote!(#template_fn_name(scope))),
+            ),
+        };
 
         quote! {
             #input_fn
 
             /// V8 callback wrapper - auto-generated by glue_v8::method
             pub fn #wrapper_name(
-                scope: &mut v8::PinScope,
-                args: v8::FunctionCallbackArguments,
-                mut rv: v8::ReturnValue,
+                __v8g_scope: &mut v8::PinScope,
+                __v8g_args: v8::FunctionCallbackArguments,
+                mut __v8g_rv: v8::ReturnValue,
             ) {
                 #state_extraction
                 #(#arg_extractions)*
@@ -257,24 +599,275 @@ pub fn method(attr: TokenStream, item: TokenStream) -> TokenStream {
             }
 
             #template_fn
+
+            #install_fn
+
+            #external_refs
         }
     } else {
+        let template_fn =
+            generate_plain_template_fn(&wrapper_name, &template_fn_name, &js_name, length);
+        let install_fn =
+            generate_install_fn(fn_name, &js_name, None, &quote!(#template_fn_name(scope)));
+
         // Standard mode: only slow path, no state
         quote! {
             #input_fn
 
             /// V8 callback wrapper - auto-generated by glue_v8::method
             pub fn #wrapper_name(
-                scope: &mut v8::PinScope,
-                args: v8::FunctionCallbackArguments,
-                mut rv: v8::ReturnValue,
+                __v8g_scope: &mut v8::PinScope,
+                __v8g_args: v8::FunctionCallbackArguments,
+                mut __v8g_rv: v8::ReturnValue,
             ) {
                 #state_extraction
                 #(#arg_extractions)*
                 #call_and_return
             }
+
+            #template_fn
+
+            #install_fn
+
+            #external_refs
         }
     };
 
-    TokenStream::from(expanded)
+    let dts_const = generate_dts_const(
+        fn_name,
+        &js_name,
+        params,
+        &input_fn.sig.output,
+        attrs.promise || is_async,
+        attrs.int64,
+    );
+
+    TokenStream::from(quote! {
+        #expanded
+        #raw_callback
+        #dts_const
+    })
+}
+
+/// Collect the TypeScript declarations of several ops into one `.d.ts` string.
+///
+/// Takes paths to `#[glue_v8::method]` functions and evaluates to a `String`
+/// with one `declare function` line per op (from their `{FN}_V8_DTS` consts).
+///
+/// # Examples
+///
+/// ```ignore
+/// let dts = glue_v8::dts!(add, timers::set_timeout, fetch::fetch_text);
+/// std::fs::write("worker-sdk/ops.d.ts", dts)?;
+/// ```
+#[proc_macro]
+pub fn dts(input: TokenStream) -> TokenStream {
+    let parser = syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated;
+    let ops = parse_macro_input!(input with parser);
+    let ops: Vec<_> = ops.into_iter().collect();
+
+    TokenStream::from(dts::generate_dts_collection(&ops))
+}
+
+/// Collect the snapshot external references of several ops.
+///
+/// Takes paths to `#[glue_v8::method]` functions, `#[glue_v8::methods]` types
+/// and `#[glue_v8::getter]`/`#[glue_v8::setter]` functions, and evaluates to a
+/// `Vec<v8::ExternalReference>` built from their `{NAME}_V8_EXTERNAL_REFS`
+/// consts (slow callbacks, and fast function plus CFunctionInfo for fast ops
+/// and methods). Pass the same list when creating and when deserializing a snapshot.
+///
+/// # Examples
+///
+/// ```ignore
+/// let refs = glue_v8::external_refs!(add, timers::set_timeout, TextEncoder, user_agent);
+/// let params = v8::CreateParams::default()
+///     .external_references(refs.into())
+///     .snapshot_blob(SNAPSHOT.into());
+/// ```
+#[proc_macro]
+pub fn external_refs(input: TokenStream) -> TokenStream {
+    let parser = syn::punctuated::Punctuated::<syn::Path, syn::Token![,]>::parse_terminated;
+    let ops = parse_macro_input!(input with parser);
+    let ops: Vec<_> = ops.into_iter().collect();
+
+    TokenStream::from(snapshot::generate_external_refs_collection(&ops))
+}
+
+/// Settle the promises of completed async ops.
+///
+/// Every `async fn` op queues its completed calls in one isolate slot, so a
+/// single call settles them all. Evaluates to the number of promises settled.
+/// Call it on every event loop turn, after polling the spawner's executor.
+///
+/// # Examples
+///
+/// ```ignore
+/// // Event loop turn:
+/// executor.run_until_stalled();
+/// glue_v8::drain!(scope);
+/// scope.perform_microtask_checkpoint();
+/// ```
+#[proc_macro]
+pub fn drain(input: TokenStream) -> TokenStream {
+    let scope = parse_macro_input!(input as syn::Expr);
+
+    TokenStream::from(async_op::generate_drain(&scope))
+}
+
+/// Expose a Rust struct as a JS class.
+///
+/// Generates the helpers used by `#[glue_v8::methods]` to store the struct in
+/// JS objects:
+/// - `{TYPE}_V8_CLASS_NAME`: the JS class name
+/// - `{Type}_v8_wrap(scope, object, value)`: store a value in an instance
+///   (`false` if the object lacks the class internal fields)
+/// - `{Type}_v8_unwrap(scope, object)`: get the `RefCell<Type>` back (brand-checked)
+///
+/// # Examples
+///
+/// ```ignore
+/// #[glue_v8::class(name = "TextEncoder")]
+/// struct Encoder;
+/// ```
+#[proc_macro_attribute]
+pub fn class(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attrs = match ClassAttrs::parse(attr) {
+        Ok(attrs) => attrs,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let input = parse_macro_input!(item as syn::ItemStruct);
+
+    TokenStream::from(class::generate_class(attrs, &input))
+}
+
+/// Expose the methods of a `#[glue_v8::class]` struct on its JS prototype.
+///
+/// Every method taking `&self` or `&mut self` becomes a prototype method.
+/// Methods are called with the value borrowed from its `RefCell`; re-entering a
+/// method while the object is mutably borrowed throws instead of panicking.
+///
+/// Helper attributes on methods:
+/// - `#[constructor]`: associated fn returning `Self` (or `Result<Self, E>`) called by `new`
+/// - `#[method(name = "jsName", promise, fast, int64 = "bigint")]`: same options as `#[glue_v8::method]`
+///
+/// `fast` methods read the value from the receiver's internal field on the Fast
+/// API path too, with the same brand check: a foreign receiver throws the
+/// slow path's `TypeError`. The Fast API requirements of `#[glue_v8::method]`
+/// apply to the other parameters and the return type.
+///
+/// Generates `{Type}::{method}_v8` callbacks plus:
+/// - `{Type}_v8_class_template(scope)`: the class FunctionTemplate (cached per isolate)
+/// - `{Type}_v8_new_instance(scope, value)`: wrap a Rust value without calling the constructor
+/// - `{TYPE}_V8_EXTERNAL_REFS`: the native callbacks of the class, for snapshots
+///
+/// # Examples
+///
+/// ```ignore
+/// #[glue_v8::methods]
+/// impl Encoder {
+///     #[constructor]
+///     fn new() -> Self {
+///         Encoder
+///     }
+///
+///     #[method(name = "encode")]
+///     fn encode(&self, input: String) -> Vec<u8> {
+///         input.into_bytes()
+///     }
+/// }
+///
+/// // Registration:
+/// let class = Encoder_v8_class_template(scope).get_function(scope).unwrap();
+/// global.set(scope, name.into(), class.into());
+/// ```
+#[proc_macro_attribute]
+pub fn methods(attr: TokenStream, item: TokenStream) -> TokenStream {
+    if !attr.is_empty() {
+        let err = syn::Error::new_spanned(
+            proc_macro2::TokenStream::from(attr),
+            "glue_v8::methods takes no arguments",
+        );
+        return err.to_compile_error().into();
+    }
+    let input = parse_macro_input!(item as syn::ItemImpl);
+
+    TokenStream::from(class::generate_methods(input))
+}
+
+/// Generate a V8 accessor getter callback for a Rust function.
+///
+/// The function takes only the optional `scope` and `state` parameters and
+/// must return a value (`Result<T, E>` throws on Err).
+///
+/// Generates:
+/// - `{fn_name}_v8_getter`: the `AccessorNameGetterCallback`
+/// - `{fn_name}_v8_accessor(..)`: an `AccessorConfiguration` for the getter,
+///   taking `(scope, &state)` when the getter has state
+/// - `{FN_NAME}_V8_EXTERNAL_REFS`: the getter callback, for snapshots
+///
+/// `setter = path` names the `#[glue_v8::setter]` of the same property: it is
+/// chained into the configuration. A setter with state must declare the same
+/// state type; a stateless setter pairs with any getter.
+///
+/// # Examples
+///
+/// ```ignore
+/// #[glue_v8::getter]
+/// fn user_agent() -> String {
+///     "OpenWorkers".to_string()
+/// }
+///
+/// // Registration:
+/// let key = v8::String::new(scope, "userAgent").unwrap();
+/// navigator.set_accessor_with_configuration(key.into(), user_agent_v8_accessor());
+/// ```
+#[proc_macro_attribute]
+pub fn getter(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attrs = match MethodAttrs::parse(attr) {
+        Ok(attrs) => attrs,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let input_fn = parse_macro_input!(item as ItemFn);
+
+    TokenStream::from(accessor::generate_getter(attrs, &input_fn))
+}
+
+/// Generate a V8 accessor setter callback for a Rust function.
+///
+/// The function takes the assigned value (converted like an op argument) plus
+/// the optional `scope` and `state` parameters. Returning `Err` throws.
+///
+/// Generates `{fn_name}_v8_setter`, registered by the getter's `setter = ..`,
+/// and `{FN_NAME}_V8_EXTERNAL_REFS` listing it for snapshots.
+/// The setter receives the state passed to the getter's `_v8_accessor`.
+///
+/// # Examples
+///
+/// ```ignore
+/// #[glue_v8::setter(state = Rc<RequestState>)]
+/// fn set_url(state: &Rc<RequestState>, value: String) -> Result<(), String> {
+///     let url = Url::parse(&value).map_err(|e| e.to_string())?;
+///     *state.url.borrow_mut() = url;
+///     Ok(())
+/// }
+///
+/// #[glue_v8::getter(state = Rc<RequestState>, setter = set_url)]
+/// fn url(state: &Rc<RequestState>) -> String {
+///     state.url.borrow().to_string()
+/// }
+///
+/// // Registration:
+/// let config = url_v8_accessor(scope, &state);
+/// template.set_accessor_with_configuration(key.into(), config);
+/// ```
+#[proc_macro_attribute]
+pub fn setter(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let attrs = match MethodAttrs::parse(attr) {
+        Ok(attrs) => attrs,
+        Err(err) => return err.to_compile_error().into(),
+    };
+    let input_fn = parse_macro_input!(item as ItemFn);
+
+    TokenStream::from(accessor::generate_setter(attrs, &input_fn))
 }
@@ -1,63 +1,438 @@
-//! Attribute parsing for glue_v8 macros.
+//! Attribute and signature parsing for glue_v8 macros.
 
 use proc_macro::TokenStream;
-use syn::Type;
+use syn::spanned::Spanned;
+use syn::{FnArg, Pat, Type};
+
+use crate::types::{is_isolate_type, is_scope_type, is_state_param_type};
+
+/// Where the state of a stateful op is kept
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub enum StateMode {
+    /// Template data holds a borrowed pointer; the caller keeps the Rc alive
+    #[default]
+    Borrowed,
+    /// Template data owns a strong Rc, released when V8 collects the data
+    Owned,
+    /// Resolved from an isolate slot, nothing stored in the template (snapshot-safe)
+    IsolateSlot,
+    /// Resolved from a slot of the current context (per realm), nothing stored in the template
+    ContextSlot,
+}
+
+impl StateMode {
+    /// The state lives in a slot rather than in the template data
+    pub fn is_slot(self) -> bool {
+        matches!(self, StateMode::IsolateSlot | StateMode::ContextSlot)
+    }
+}
+
+/// JS representation of `i64`/`u64` values (`int64 = "number" | "bigint"`)
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+pub enum Int64Repr {
+    /// Without `int64`: converted via serde_v8 like other values (Numbers are
+    /// truncated, BigInts accepted; returned as a Number, or a BigInt beyond
+    /// `Number.MAX_SAFE_INTEGER`). Not allowed with a Fast API path.
+    #[default]
+    Serde,
+    /// A Number; values beyond `Number.MAX_SAFE_INTEGER` throw a RangeError
+    Number,
+    /// A BigInt; values out of the Rust type's range throw a RangeError
+    BigInt,
+}
 
 /// Parsed attributes for #[glue_v8::method]
+#[derive(Default)]
 pub struct MethodAttrs {
     pub js_name: Option<String>,
     pub state_type: Option<Type>,
     pub promise: bool,
     pub fast: bool,
+    /// `fast(require)`: missing the Fast API path is a compile error
+    pub fast_required: bool,
+    pub spawn: Option<Type>,
+    /// `setter = path` on a getter: the `#[glue_v8::setter]` of the same property
+    pub setter: Option<syn::Path>,
+    pub state_mode: StateMode,
+    pub int64: Int64Repr,
+    /// Each key given (`name`, `state`, `fast`, ...) with its span, in order
+    pub keys: Vec<(String, proc_macro2::Span)>,
 }
 
 impl MethodAttrs {
-    pub fn parse(attr: TokenStream) -> Self {
+    /// Parse the attribute arguments, or a bare string literal (the JS name).
+    ///
+    /// Unknown or malformed arguments are reported at their span.
+    pub fn parse(attr: TokenStream) -> syn::Result<Self> {
         use std::cell::RefCell;
 
+        if let Ok(lit) = syn::parse::<syn::LitStr>(attr.clone()) {
+            return Ok(Self {
+                js_name: Some(js_identifier(&lit)?),
+                keys: vec![("name".to_string(), lit.span())],
+                ..Self::default()
+            });
+        }
+
         let js_name: RefCell<Option<String>> = RefCell::new(None);
         let state_type: RefCell<Option<Type>> = RefCell::new(None);
         let promise: RefCell<bool> = RefCell::new(false);
         let fast: RefCell<bool> = RefCell::new(false);
+        let fast_required: RefCell<bool> = RefCell::new(false);
+        let spawn: RefCell<Option<Type>> = RefCell::new(None);
+        let setter: RefCell<Option<syn::Path>> = RefCell::new(None);
+        let state_mode: RefCell<Option<StateMode>> = RefCell::new(None);
+        let int64: RefCell<Int64Repr> = RefCell::new(Int64Repr::default());
+        let keys: RefCell<Vec<(String, proc_macro2::Span)>> = RefCell::new(Vec::new());
+
+        // `owned`, `snapshot` and `state(slot = ..)` select exclusive state modes
+        let set_state_mode = |mode: StateMode, span: proc_macro2::Span| {
+            let mut state_mode = state_mode.borrow_mut();
+            match *state_mode {
+                Some(previous) if previous != mode => Err(syn::Error::new(
+                    span,
+                    "`owned`, `snapshot` and `state(slot = ..)` state modes are exclusive",
+                )),
+                _ => {
+                    *state_mode = Some(mode);
+                    Ok(())
+                }
+            }
+        };
 
         if !attr.is_empty() {
             let parser = syn::meta::parser(|meta| {
+                let key = meta.path.segments.iter().map(|s| s.ident.to_string());
+                let key = key.collect::<Vec<_>>().join("::");
+                if keys.borrow().iter().any(|(previous, _)| *previous == key) {
+                    return Err(syn::Error::new(
+                        meta.path.span(),
+                        format!("duplicate `{}`", key),
+                    ));
+                }
+                keys.borrow_mut().push((key, meta.path.span()));
+
                 if meta.path.is_ident("state") {
+                    // `state(slot = isolate|context)`, optionally followed by `= Type`
+                    if meta.input.peek(syn::token::Paren) {
+                        meta.parse_nested_meta(|nested| {
+                            if !nested.path.is_ident("slot") {
+                                return Err(nested.error("expected `slot = isolate|context`"));
+                            }
+                            let slot: syn::Ident = nested.value()?.parse()?;
+                            let mode = if slot == "isolate" {
+                                StateMode::IsolateSlot
+                            } else if slot == "context" {
+                                StateMode::ContextSlot
+                            } else {
+                                return Err(syn::Error::new(
+                                    slot.span(),
+                                    "expected `isolate` or `context`",
+                                ));
+                            };
+                            set_state_mode(mode, slot.span())
+                        })?;
+
+                        if !meta.input.peek(syn::Token![=]) {
+                            return Ok(());
+                        }
+                    }
+
                     // Parse as type (handles generics like Rc<T>)
-                    let value: Type = meta.value()?.parse()?;
+                    let value = meta.value()?;
+                    if value.is_empty() || value.peek(syn::Token![,]) {
+                        return Err(syn::Error::new(
+                            meta.path.span(),
+                            "expected a state type after `state =`",
+                        ));
+                    }
+                    let value: Type = value.parse()?;
                     *state_type.borrow_mut() = Some(value);
                     Ok(())
                 } else if meta.path.is_ident("name") {
                     let value: syn::LitStr = meta.value()?.parse()?;
-                    *js_name.borrow_mut() = Some(value.value());
+                    *js_name.borrow_mut() = Some(js_identifier(&value)?);
                     Ok(())
                 } else if meta.path.is_ident("promise") {
                     *promise.borrow_mut() = true;
                     Ok(())
                 } else if meta.path.is_ident("fast") {
                     *fast.borrow_mut() = true;
+                    // `fast(require)`
+                    if meta.input.peek(syn::token::Paren) {
+                        meta.parse_nested_meta(|nested| {
+                            if !nested.path.is_ident("require") {
+                                return Err(nested.error("expected `require`"));
+                            }
+                            *fast_required.borrow_mut() = true;
+                            Ok(())
+                        })?;
+                    }
+                    Ok(())
+                } else if meta.path.is_ident("owned") {
+                    set_state_mode(StateMode::Owned, meta.path.span())
+                } else if meta.path.is_ident("snapshot") {
+                    set_state_mode(StateMode::IsolateSlot, meta.path.span())
+                } else if meta.path.is_ident("spawn") {
+                    // The spawner type, implementing the embedder's spawner trait
+                    let value: Type = meta.value()?.parse()?;
+                    *spawn.borrow_mut() = Some(value);
+                    Ok(())
+                } else if meta.path.is_ident("setter") {
+                    let value: syn::Path = meta.value()?.parse()?;
+                    *setter.borrow_mut() = Some(value);
+                    Ok(())
+                } else if meta.path.is_ident("int64") {
+                    let value: syn::LitStr = meta.value()?.parse()?;
+                    *int64.borrow_mut() = match value.value().as_str() {
+                        "number" => Int64Repr::Number,
+                        "bigint" => Int64Repr::BigInt,
+                        _ => {
+                            return Err(syn::Error::new(
+                                value.span(),
+                                "expected `\"number\"` or `\"bigint\"`",
+                            ));
+                        }
+                    };
                     Ok(())
                 } else {
                     Err(meta.error(
-                        "expected `state = Type`, `name = \"jsName\"`, `promise`, or `fast`",
+                        "expected `state = Type`, `state(slot = isolate|context)`, `name = \"jsName\"`, `promise`, `fast`, `fast(require)`, `owned`, `snapshot`, `spawn = Type`, `setter = path`, or `int64 = \"number\"|\"bigint\"`",
                     ))
                 }
             });
 
-            // Try parsing as meta items
-            if syn::parse::Parser::parse(parser, attr.clone()).is_err() {
-                // Fall back to bare string literal
-                if let Ok(lit) = syn::parse::<syn::LitStr>(attr) {
-                    *js_name.borrow_mut() = Some(lit.value());
-                }
-            }
+            syn::parse::Parser::parse(parser, attr)?;
         }
 
-        Self {
+        Ok(Self {
             js_name: js_name.into_inner(),
             state_type: state_type.into_inner(),
             promise: promise.into_inner(),
             fast: fast.into_inner(),
+            fast_required: fast_required.into_inner(),
+            spawn: spawn.into_inner(),
+            setter: setter.into_inner(),
+            state_mode: state_mode.into_inner().unwrap_or_default(),
+            int64: int64.into_inner(),
+            keys: keys.into_inner(),
+        })
+    }
+}
+
+/// Value of a `name = ".."` argument, which must be a JS identifier: it becomes
+/// the property key of the op and its name in the TypeScript declarations
+fn js_identifier(lit: &syn::LitStr) -> syn::Result<String> {
+    let name = lit.value();
+    let mut chars = name.chars();
+    let is_identifier = chars
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$');
+
+    if !is_identifier {
+        return Err(syn::Error::new(
+            lit.span(),
+            format!("`{}` is not a valid JavaScript identifier", name),
+        ));
+    }
+
+    Ok(name)
+}
+
+/// Parsed attributes for #[glue_v8::class]
+#[derive(Default)]
+pub struct ClassAttrs {
+    pub js_name: Option<String>,
+}
+
+impl ClassAttrs {
+    pub fn parse(attr: TokenStream) -> syn::Result<Self> {
+        let mut attrs = Self::default();
+
+        if !attr.is_empty() {
+            let parser = syn::meta::parser(|meta| {
+                if meta.path.is_ident("name") {
+                    let value: syn::LitStr = meta.value()?.parse()?;
+                    attrs.js_name = Some(js_identifier(&value)?);
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `name = \"JsName\"`"))
+                }
+            });
+
+            syn::parse::Parser::parse(parser, attr)?;
+        }
+
+        Ok(attrs)
+    }
+}
+
+/// Role of an op parameter, deciding what the wrapper passes for it
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ParamRole {
+    /// `&mut v8::PinScope` (or `#[scope]`): the callback scope
+    Scope,
+    /// `&v8::Isolate` / `&mut v8::Isolate` (or `#[isolate]`): derefed from the scope
+    Isolate,
+    /// The op state (`state = Type`): matched by type, or named `state` in stateful ops
+    State,
+    /// `#[state]`: resolved from the state registry
+    RegistryState,
+    /// Converted from the next JS argument
+    Arg,
+}
+
+/// Parameters of an op signature, split into JS arguments and special params.
+pub struct OpParams {
+    /// Parameters extracted from JS arguments, in declaration order
+    pub params: Vec<(syn::Ident, Box<Type>)>,
+    /// The signature takes the scope (provided by the V8 callback)
+    pub has_scope: bool,
+    /// The signature takes the isolate (provided by the V8 callback)
+    pub has_isolate: bool,
+    /// The signature takes the state (extracted from FunctionTemplate data)
+    pub has_state: bool,
+    /// Type of the state parameter
+    pub state_param: Option<Box<Type>>,
+    /// The state parameter is a `&mut T`, borrowed from the `RefCell` state for the call
+    pub state_mut: bool,
+    /// Parameters marked `#[state]`, resolved from the context/isolate slot registry
+    pub states: Vec<(syn::Ident, Box<Type>)>,
+    /// Every parameter (receivers excluded) in declaration order, with its role
+    pub roles: Vec<(syn::Ident, ParamRole)>,
+}
+
+impl OpParams {
+    /// Classify the parameters of a signature.
+    ///
+    /// Roles come from the `#[scope]`, `#[isolate]` and `#[state]` markers, then
+    /// from the type (`&mut v8::PinScope`, `&v8::Isolate`, the declared state
+    /// type). When the op has state (`attrs`), a parameter named `state` is the
+    /// state too; without a state type, a reference named `state` is an error
+    /// rather than a JS argument. Everything else is a JS argument, whatever its
+    /// name. Receivers (`self`, `&self`, `&mut self`) are skipped.
+    pub fn from_signature(sig: &syn::Signature, attrs: Option<&MethodAttrs>) -> syn::Result<Self> {
+        let state_type = attrs.and_then(|attrs| attrs.state_type.as_ref());
+        let stateful =
+            state_type.is_some() || attrs.is_some_and(|attrs| attrs.state_mode.is_slot());
+
+        let mut op = Self {
+            params: Vec::new(),
+            has_scope: false,
+            has_isolate: false,
+            has_state: false,
+            state_param: None,
+            state_mut: false,
+            states: Vec::new(),
+            roles: Vec::new(),
+        };
+
+        for (index, arg) in sig.inputs.iter().enumerate() {
+            let FnArg::Typed(pat_type) = arg else {
+                continue;
+            };
+            let (name, is_binding) = param_name(&pat_type.pat, index)?;
+            let ty = &pat_type.ty;
+            let has_marker =
+                |marker: &str| pat_type.attrs.iter().any(|a| a.path().is_ident(marker));
+
+            let role = if has_marker("state") {
+                ParamRole::RegistryState
+            } else if has_marker("scope") || is_scope_type(ty) {
+                ParamRole::Scope
+            } else if has_marker("isolate") || is_isolate_type(ty) {
+                ParamRole::Isolate
+            } else if state_type.is_some_and(|state_ty| is_state_param_type(ty, state_ty))
+                || (stateful && is_binding && name == "state")
+            {
+                ParamRole::State
+            } else if attrs.is_some()
+                && is_binding
+                && name == "state"
+                && matches!(&**ty, Type::Reference(_))
+            {
+                return Err(syn::Error::new_spanned(
+                    pat_type,
+                    "`state` parameter without a state type: add `state = Type` to the attribute",
+                ));
+            } else {
+                ParamRole::Arg
+            };
+
+            let duplicate = match role {
+                ParamRole::Scope => op.has_scope.then_some("the scope"),
+                ParamRole::Isolate => op.has_isolate.then_some("the isolate"),
+                ParamRole::State => op.has_state.then_some("the state"),
+                ParamRole::RegistryState | ParamRole::Arg => None,
+            };
+            if let Some(what) = duplicate {
+                return Err(syn::Error::new_spanned(
+                    pat_type,
+                    format!("{} is already taken by another parameter", what),
+                ));
+            }
+
+            match role {
+                ParamRole::Scope => op.has_scope = true,
+                ParamRole::Isolate => op.has_isolate = true,
+                ParamRole::State => {
+                    op.has_state = true;
+                    op.state_param = Some(ty.clone());
+                    op.state_mut = matches!(
+                        &**ty,
+                        Type::Reference(reference) if reference.mutability.is_some()
+                    );
+                }
+                ParamRole::RegistryState => op.states.push((name.clone(), ty.clone())),
+                ParamRole::Arg => op.params.push((name.clone(), ty.clone())),
+            }
+            op.roles.push((name, role));
+        }
+
+        Ok(op)
+    }
+}
+
+/// Name the wrapper binds a parameter to, and whether it is the user's own binding.
+///
+/// `x`, `mut x` and `ref x` keep their name. Destructuring patterns (`(a, b)`,
+/// `Point { x, y }`, `_`) get a synthetic `__v8g_arg{index}`: the wrapper converts
+/// the whole value and the op destructures it.
+fn param_name(pat: &Pat, index: usize) -> syn::Result<(syn::Ident, bool)> {
+    match pat {
+        Pat::Ident(pat_ident) if pat_ident.subpat.is_none() => Ok((pat_ident.ident.clone(), true)),
+        Pat::Ident(_)
+        | Pat::Tuple(_)
+        | Pat::TupleStruct(_)
+        | Pat::Struct(_)
+        | Pat::Slice(_)
+        | Pat::Reference(_)
+        | Pat::Paren(_)
+        | Pat::Path(_)
+        | Pat::Wild(_) => Ok((
+            syn::Ident::new(&format!("__v8g_arg{}", index), pat.span()),
+            false,
+        )),
+        _ => Err(syn::Error::new_spanned(
+            pat,
+            "unsupported parameter pattern: use a binding or an irrefutable destructuring pattern",
+        )),
+    }
+}
+
+/// Parameter markers, which are not real attributes once the op is emitted
+const PARAM_MARKERS: &[&str] = &["state", "scope", "isolate"];
+
+/// Remove the `#[state]`, `#[scope]` and `#[isolate]` parameter markers.
+pub fn strip_param_markers(sig: &mut syn::Signature) {
+    for arg in sig.inputs.iter_mut() {
+        if let FnArg::Typed(pat_type) = arg {
+            pat_type.attrs.retain(|attr| {
+                !PARAM_MARKERS
+                    .iter()
+                    .any(|marker| attr.path().is_ident(marker))
+            });
         }
     }
 }
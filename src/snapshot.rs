@@ -0,0 +1,164 @@
+//! V8 snapshot support: raw callbacks and external reference tables.
+//!
+//! A snapshot stores templates with the addresses of their native callbacks,
+//! which must be listed in `v8::ExternalReferences` when the snapshot is
+//! created and deserialized. `FunctionTemplate::builder` maps the wrapper to
+//! an adapter that can't be named in a const, so templates are built with
+//! `builder_raw` from a generated `extern "C"` callback instead, and every op
+//! lists its addresses in a `{FN}_V8_EXTERNAL_REFS` const. Classes do the same
+//! for their constructor and methods (`{TYPE}_V8_EXTERNAL_REFS`).
+//!
+//! Accessor configurations only take Rust callbacks (mapped by `MapFnTo`), so
+//! the consts of getters and setters hold functions resolving the mapped
+//! address instead, called by `glue_v8::external_refs!`.
+
+use quote::quote;
+
+use crate::class::screaming_snake_case;
+
+/// Name of the raw (C ABI) callback for a wrapper (`{fn}_v8` -> `{fn}_v8_raw`)
+pub fn raw_callback_name(wrapper_name: &syn::Ident) -> syn::Ident {
+    syn::Ident::new(&format!("{}_raw", wrapper_name), wrapper_name.span())
+}
+
+/// Name of the const listing the external references of an op, accessor or class
+/// (`add` -> `ADD_V8_EXTERNAL_REFS`, `TextEncoder` -> `TEXT_ENCODER_V8_EXTERNAL_REFS`)
+pub fn external_refs_const_name(ident: &syn::Ident) -> syn::Ident {
+    syn::Ident::new(
+        &format!(
+            "{}_V8_EXTERNAL_REFS",
+            screaming_snake_case(&ident.to_string())
+        ),
+        ident.span(),
+    )
+}
+
+/// Generate the raw `extern "C"` callback calling the slow-path wrapper.
+///
+/// Equivalent to the adapter generated by `MapFnTo`, but nameable in consts.
+/// `callee` is the path of the wrapper (`Self::{fn}_v8` for class methods).
+pub fn generate_raw_callback(
+    wrapper_name: &syn::Ident,
+    callee: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let raw_name = raw_callback_name(wrapper_name);
+
+    quote! {
+        /// Raw V8 callback (C ABI) for the slow path - auto-generated by glue_v8
+        ///
+        /// Templates are built from this address so it can be listed in snapshot
+        /// external references.
+        #[doc(hidden)]
+        pub unsafe extern "C" fn #raw_name(info: *const v8::FunctionCallbackInfo) {
+            // SAFETY: V8 passes a valid FunctionCallbackInfo for the duration of the call
+            let info = unsafe { &*info };
+            let scope = std::pin::pin!(unsafe { v8::CallbackScope::new(info) });
+            let mut scope = scope.init();
+            let args = v8::FunctionCallbackArguments::from_function_callback_info(info);
+            let rv = v8::ReturnValue::from_function_callback_info(info);
+            #callee(&mut scope, args, rv);
+        }
+    }
+}
+
+/// External references of the fast functions of an op or method: the address
+/// and CFunctionInfo of each overload.
+pub fn fast_external_refs(cfunctions: &[proc_macro2::TokenStream]) -> proc_macro2::TokenStream {
+    let fast_refs = cfunctions.iter().map(|cfunction| {
+        quote! {
+            v8::ExternalReference {
+                pointer: #cfunction.address() as *mut std::ffi::c_void,
+            },
+            v8::ExternalReference {
+                type_info: #cfunction.type_info() as *const v8::fast_api::CFunctionInfo,
+            },
+        }
+    });
+
+    quote!(#(#fast_refs)*)
+}
+
+/// Generate the `{FN}_V8_EXTERNAL_REFS` const for an op.
+///
+/// Lists the raw slow-path callback, plus the address and CFunctionInfo of each
+/// fast function (one per overload) when the op has a Fast API path.
+pub fn generate_external_refs(
+    fn_name: &syn::Ident,
+    wrapper_name: &syn::Ident,
+    cfunction_names: &[syn::Ident],
+) -> proc_macro2::TokenStream {
+    let const_name = external_refs_const_name(fn_name);
+    let raw_name = raw_callback_name(wrapper_name);
+    let cfunctions: Vec<_> = cfunction_names.iter().map(|cf| quote!(#cf)).collect();
+    let fast_refs = fast_external_refs(&cfunctions);
+
+    quote! {
+        /// External references of this op, for V8 snapshots - auto-generated by glue_v8::method
+        pub const #const_name: &[v8::ExternalReference] = &[
+            v8::ExternalReference { function: #raw_name },
+            #fast_refs
+        ];
+    }
+}
+
+/// Generate the `{FN}_V8_EXTERNAL_REFS` const for a getter or setter callback.
+///
+/// `callback_type` is the V8 callback type the Rust callback is mapped to
+/// (`AccessorNameGetterCallback` or `AccessorNameSetterCallback`). The address
+/// of the mapped callback is only known at runtime, so the const holds a
+/// function resolving it.
+pub fn generate_accessor_external_refs(
+    fn_name: &syn::Ident,
+    callback_name: &syn::Ident,
+    callback_type: &proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let const_name = external_refs_const_name(fn_name);
+
+    quote! {
+        /// External references of this accessor, for V8 snapshots - auto-generated by glue_v8
+        ///
+        /// Resolved by `glue_v8::external_refs!`.
+        pub const #const_name: &[fn() -> v8::ExternalReference] = &[|| {
+            let callback = <_ as v8::MapFnTo<v8::#callback_type>>::map_fn_to(#callback_name);
+            v8::ExternalReference {
+                pointer: callback as *mut std::ffi::c_void,
+            }
+        }];
+    }
+}
+
+/// Expand `glue_v8::external_refs!(path::to::op, ...)` to a `Vec<v8::ExternalReference>`.
+///
+/// Ops and classes list their references, accessors list functions resolving them.
+pub fn generate_external_refs_collection(ops: &[syn::Path]) -> proc_macro2::TokenStream {
+    let consts = ops.iter().map(|op| {
+        let mut path = op.clone();
+        let last = path.segments.last_mut().unwrap();
+        last.ident = external_refs_const_name(&last.ident);
+        path
+    });
+
+    quote! {
+        {
+            trait ResolveExternalReference {
+                fn resolve(&self) -> v8::ExternalReference;
+            }
+
+            impl ResolveExternalReference for v8::ExternalReference {
+                fn resolve(&self) -> v8::ExternalReference {
+                    *self
+                }
+            }
+
+            impl ResolveExternalReference for fn() -> v8::ExternalReference {
+                fn resolve(&self) -> v8::ExternalReference {
+                    self()
+                }
+            }
+
+            let mut refs: Vec<v8::ExternalReference> = Vec::new();
+            #(refs.extend(#consts.iter().map(ResolveExternalReference::resolve));)*
+            refs
+        }
+    }
+}
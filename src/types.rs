@@ -19,15 +19,13 @@ pub fn get_v8_local_inner_type(ty: &Type) -> Option<String> {
                 None
             };
 
-        if let Some(segment) = local_segment {
-            if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
-                if let Some(syn::GenericArgument::Type(Type::Path(inner_path))) = args.args.first()
-                {
-                    // Get the inner type name (e.g., v8::Function -> Function)
-                    if let Some(last_segment) = inner_path.path.segments.last() {
-                        return Some(last_segment.ident.to_string());
-                    }
-                }
+        if let Some(segment) = local_segment
+            && let syn::PathArguments::AngleBracketed(args) = &segment.arguments
+            && let Some(syn::GenericArgument::Type(Type::Path(inner_path))) = args.args.first()
+        {
+            // Get the inner type name (e.g., v8::Function -> Function)
+            if let Some(last_segment) = inner_path.path.segments.last() {
+                return Some(last_segment.ident.to_string());
             }
         }
     }
@@ -37,63 +35,263 @@ pub fn get_v8_local_inner_type(ty: &Type) -> Option<String> {
 
 /// Check if the return type is Result<T, E>
 pub fn is_result_type(ty: &Type) -> bool {
-    if let Type::Path(type_path) = ty {
-        if let Some(segment) = type_path.path.segments.last() {
-            return segment.ident == "Result";
-        }
+    if let Type::Path(type_path) = ty
+        && let Some(segment) = type_path.path.segments.last()
+    {
+        return segment.ident == "Result";
     }
     false
 }
 
+/// Check if type is `Result<T, E>` and return the `T`
+pub fn get_result_ok_type(ty: &Type) -> Option<&Type> {
+    get_pointer_inner_type(ty, "Result")
+}
+
 /// Check if type is Option<T> and return the inner type
 pub fn get_option_inner_type(ty: &Type) -> Option<&Type> {
-    if let Type::Path(type_path) = ty {
-        if let Some(segment) = type_path.path.segments.last() {
-            if segment.ident == "Option" {
-                if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
-                    if let Some(syn::GenericArgument::Type(inner_ty)) = args.args.first() {
-                        return Some(inner_ty);
-                    }
-                }
-            }
-        }
+    if let Type::Path(type_path) = ty
+        && let Some(segment) = type_path.path.segments.last()
+        && segment.ident == "Option"
+        && let syn::PathArguments::AngleBracketed(args) = &segment.arguments
+        && let Some(syn::GenericArgument::Type(inner_ty)) = args.args.first()
+    {
+        return Some(inner_ty);
     }
     None
 }
 
-/// Check if type is Rc<T> and return the inner type
-pub fn get_rc_inner_type(ty: &Type) -> Option<&Type> {
-    if let Type::Path(type_path) = ty {
-        if let Some(segment) = type_path.path.segments.last() {
-            if segment.ident == "Rc" {
-                if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
-                    if let Some(syn::GenericArgument::Type(inner_ty)) = args.args.first() {
-                        return Some(inner_ty);
-                    }
-                }
-            }
+/// Number of required JS parameters: everything up to the last non-`Option` parameter.
+///
+/// Trailing `Option<T>` parameters may be omitted by the caller; this is the
+/// `length` of the JS function.
+pub fn required_param_count(params: &[(syn::Ident, Box<Type>)]) -> usize {
+    params
+        .iter()
+        .rposition(|(_, ty)| get_option_inner_type(ty).is_none())
+        .map_or(0, |i| i + 1)
+}
+
+/// Check if type is `{name}<T>` (e.g. `Rc<T>`) and return the inner type
+fn get_pointer_inner_type<'a>(ty: &'a Type, name: &str) -> Option<&'a Type> {
+    if let Type::Path(type_path) = ty
+        && let Some(segment) = type_path.path.segments.last()
+        && segment.ident == name
+        && let syn::PathArguments::AngleBracketed(args) = &segment.arguments
+        && let Some(syn::GenericArgument::Type(inner_ty)) = args.args.first()
+    {
+        return Some(inner_ty);
+    }
+    None
+}
+
+/// Check if type is RefCell<T>
+pub fn is_refcell_type(ty: &Type) -> bool {
+    get_pointer_inner_type(ty, "RefCell").is_some()
+}
+
+/// Get the last path segment name of the referent of a reference type
+fn reference_target_name(ty: &Type) -> Option<(String, bool)> {
+    if let Type::Reference(reference) = ty
+        && let Type::Path(type_path) = &*reference.elem
+        && let Some(segment) = type_path.path.segments.last()
+    {
+        return Some((segment.ident.to_string(), reference.mutability.is_some()));
+    }
+    None
+}
+
+/// Check if type is `&mut v8::PinScope` (the callback scope)
+pub fn is_scope_type(ty: &Type) -> bool {
+    matches!(reference_target_name(ty), Some((name, true)) if name == "PinScope")
+}
+
+/// Check if type is `&v8::Isolate` or `&mut v8::Isolate`
+pub fn is_isolate_type(ty: &Type) -> bool {
+    matches!(reference_target_name(ty), Some((name, _)) if name == "Isolate")
+}
+
+/// Check if type is `i64` or `u64`, and return whether it is signed
+pub fn get_int64_signedness(ty: &Type) -> Option<bool> {
+    if let Type::Path(type_path) = ty
+        && let Some(ident) = type_path.path.get_ident()
+    {
+        if ident == "i64" {
+            return Some(true);
+        } else if ident == "u64" {
+            return Some(false);
         }
     }
     None
 }
 
+/// Types converted to and from JS directly, without serde_v8
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PrimitiveType {
+    Bool,
+    I32,
+    U32,
+    F32,
+    F64,
+    String,
+}
+
+/// Check if type is `bool`, `i32`, `u32`, `f32`, `f64` or `String`
+pub fn get_primitive_type(ty: &Type) -> Option<PrimitiveType> {
+    let Type::Path(type_path) = ty else {
+        return None;
+    };
+    let ident = type_path.path.get_ident()?;
+
+    match ident.to_string().as_str() {
+        "bool" => Some(PrimitiveType::Bool),
+        "i32" => Some(PrimitiveType::I32),
+        "u32" => Some(PrimitiveType::U32),
+        "f32" => Some(PrimitiveType::F32),
+        "f64" => Some(PrimitiveType::F64),
+        "String" => Some(PrimitiveType::String),
+        _ => None,
+    }
+}
+
+/// Check if type is `&str`
+pub fn is_str_ref_type(ty: &Type) -> bool {
+    matches!(reference_target_name(ty), Some((name, false)) if name == "str")
+}
+
+/// Compare types by tokens, ignoring reference lifetimes
+fn same_type(a: &Type, b: &Type) -> bool {
+    match (a, b) {
+        (Type::Reference(a), Type::Reference(b)) => {
+            a.mutability.is_some() == b.mutability.is_some() && same_type(&a.elem, &b.elem)
+        }
+        _ => quote!(#a).to_string() == quote!(#b).to_string(),
+    }
+}
+
+/// Check if a parameter type takes the declared state type `state_ty`.
+///
+/// Matches the state type itself (`Rc<T>`/`Arc<T>` by value for async ops, or
+/// `&'static T`), a reference to it or to its inner `T`, and `&mut T` for
+/// `RefCell<T>` state.
+pub fn is_state_param_type(param_ty: &Type, state_ty: &Type) -> bool {
+    let kind = StateKind::of(state_ty);
+
+    if !matches!(kind, StateKind::Value(_)) && same_type(param_ty, state_ty) {
+        return true;
+    }
+
+    let Type::Reference(reference) = param_ty else {
+        return false;
+    };
+    let inner = kind.inner();
+
+    if reference.mutability.is_some() {
+        return get_pointer_inner_type(inner, "RefCell")
+            .is_some_and(|cell_inner| same_type(&reference.elem, cell_inner));
+    }
+
+    same_type(&reference.elem, state_ty) || same_type(&reference.elem, inner)
+}
+
+/// Infer the state type from the `state` parameter: `&T` and `T` give `T`,
+/// `&'static T` is kept as is.
+///
+/// `&mut T` can't be inferred, its state type wraps `T` in a `RefCell`.
+pub fn infer_state_type(param_ty: &Type) -> Option<Type> {
+    match param_ty {
+        Type::Reference(reference) if reference.mutability.is_some() => None,
+        Type::Reference(reference)
+            if reference
+                .lifetime
+                .as_ref()
+                .is_some_and(|lifetime| lifetime.ident == "static") =>
+        {
+            Some(param_ty.clone())
+        }
+        Type::Reference(reference) => Some((*reference.elem).clone()),
+        ty => Some(ty.clone()),
+    }
+}
+
+/// Get the state type `T` of a `#[state]` parameter, and whether it is taken by value.
+///
+/// Registry state is stored as `Rc<T>`; the parameter is `&T`, `&Rc<T>` or `Rc<T>`.
+pub fn get_registry_state_type(ty: &Type) -> Option<(&Type, bool)> {
+    match ty {
+        Type::Reference(reference) if reference.mutability.is_none() => Some((
+            get_pointer_inner_type(&reference.elem, "Rc").unwrap_or(&reference.elem),
+            false,
+        )),
+        _ => get_pointer_inner_type(ty, "Rc").map(|inner| (inner, true)),
+    }
+}
+
+/// How op state is held, from the `state = Type` attribute.
+///
+/// Template data always points to the inner `T`; the kind decides how that
+/// pointer is created, released and handed to the op.
+#[derive(Clone, Copy)]
+pub enum StateKind<'a> {
+    /// `Rc<T>`: reference-counted, single-threaded
+    Rc(&'a Type),
+    /// `Arc<T>`: reference-counted, shareable across isolates and threads
+    Arc(&'a Type),
+    /// `&'static T`: never released
+    Static(&'a Type),
+    /// Plain `T`: boxed and owned by the template
+    Value(&'a Type),
+}
+
+impl<'a> StateKind<'a> {
+    pub fn of(ty: &'a Type) -> Self {
+        if let Some(inner) = get_pointer_inner_type(ty, "Rc") {
+            StateKind::Rc(inner)
+        } else if let Some(inner) = get_pointer_inner_type(ty, "Arc") {
+            StateKind::Arc(inner)
+        } else if let Type::Reference(reference) = ty
+            && reference.mutability.is_none()
+        {
+            StateKind::Static(&reference.elem)
+        } else {
+            StateKind::Value(ty)
+        }
+    }
+
+    /// The `T` pointed to by the template data
+    pub fn inner(&self) -> &'a Type {
+        match *self {
+            StateKind::Rc(ty)
+            | StateKind::Arc(ty)
+            | StateKind::Static(ty)
+            | StateKind::Value(ty) => ty,
+        }
+    }
+
+    /// Whether the op receives `&T` directly (rather than a reference to the pointer)
+    pub fn is_reference(&self) -> bool {
+        matches!(self, StateKind::Static(_) | StateKind::Value(_))
+    }
+}
+
 /// Generate extraction code for a V8 Local type with type check
 pub fn v8_local_extraction(
     name: &syn::Ident,
-    idx: i32,
+    source: &proc_macro2::TokenStream,
+    label: &str,
     v8_type: &str,
     check_method: &str,
 ) -> proc_macro2::TokenStream {
     let v8_type_ident = syn::Ident::new(v8_type, name.span());
     let check_ident = syn::Ident::new(check_method, name.span());
-    let error_msg = format!("argument {} must be a {}", idx, v8_type);
+    let error_msg = format!("{} must be a {}", label, v8_type);
 
     quote! {
-        let __v8g_tmp = args.get(#idx);
+        let __v8g_tmp = #source;
         if !__v8g_tmp.#check_ident() {
-            let msg = v8::String::new(scope, #error_msg).unwrap();
-            let err = v8::Exception::type_error(scope, msg);
-            scope.throw_exception(err);
+            let msg = v8::String::new(__v8g_scope, #error_msg).unwrap();
+            let err = v8::Exception::type_error(__v8g_scope, msg);
+            __v8g_scope.throw_exception(err);
             return;
         }
         let #name: v8::Local<v8::#v8_type_ident> = __v8g_tmp.try_into().unwrap();
@@ -0,0 +1,10 @@
+//! Compile-fail tests for the macro diagnostics
+//!
+//! Each `tests/ui/*.rs` case must fail to compile with the error in its
+//! `.stderr` file. Regenerate them with `TRYBUILD=overwrite cargo test --test compile_fail`.
+
+#[test]
+fn compile_fail() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+}
@@ -4,12 +4,15 @@
 
 use std::pin::pin;
 use std::rc::Rc;
-use std::sync::Once;
+use std::sync::{Arc, Once};
 
 static INIT: Once = Once::new();
 
 fn init_v8() {
     INIT.call_once(|| {
+        // --expose_gc enables request_garbage_collection_for_testing,
+        // --allow-natives-syntax lets tests force optimization (Fast API calls)
+        v8::V8::set_flags_from_string("--expose_gc --allow-natives-syntax");
         let platform = v8::new_default_platform(0, false).make_shared();
         v8::V8::initialize_platform(platform);
         v8::V8::initialize();
@@ -256,12 +259,12 @@ fn test_result_err_throws() {
 
     let func = v8::Function::new(&mut tc, parse_number_v8).unwrap();
     let global = tc.get_current_context().global(&tc);
-    let key = v8::String::new(&mut tc, "parseNumber").unwrap();
-    global.set(&mut tc, key.into(), func.into());
+    let key = v8::String::new(&tc, "parseNumber").unwrap();
+    global.set(&tc, key.into(), func.into());
 
-    let code = v8::String::new(&mut tc, "parseNumber('not a number')").unwrap();
-    let script = v8::Script::compile(&mut tc, code, None).unwrap();
-    let result = script.run(&mut tc);
+    let code = v8::String::new(&tc, "parseNumber('not a number')").unwrap();
+    let script = v8::Script::compile(&tc, code, None).unwrap();
+    let result = script.run(&tc);
 
     assert!(result.is_none());
     assert!(tc.has_caught());
@@ -322,13 +325,13 @@ fn test_callback_type_error() {
 
     let func = v8::Function::new(&mut tc, call_twice_v8).unwrap();
     let global = tc.get_current_context().global(&tc);
-    let key = v8::String::new(&mut tc, "callTwice").unwrap();
-    global.set(&mut tc, key.into(), func.into());
+    let key = v8::String::new(&tc, "callTwice").unwrap();
+    global.set(&tc, key.into(), func.into());
 
     // Pass a string instead of function
-    let code = v8::String::new(&mut tc, "callTwice('not a function', 5)").unwrap();
-    let script = v8::Script::compile(&mut tc, code, None).unwrap();
-    let result = script.run(&mut tc);
+    let code = v8::String::new(&tc, "callTwice('not a function', 5)").unwrap();
+    let script = v8::Script::compile(&tc, code, None).unwrap();
+    let result = script.run(&tc);
 
     assert!(result.is_none());
     assert!(tc.has_caught());
@@ -464,13 +467,13 @@ fn test_wrong_arg_type() {
 
     let func = v8::Function::new(&mut tc, add_v8).unwrap();
     let global = tc.get_current_context().global(&tc);
-    let key = v8::String::new(&mut tc, "add").unwrap();
-    global.set(&mut tc, key.into(), func.into());
+    let key = v8::String::new(&tc, "add").unwrap();
+    global.set(&tc, key.into(), func.into());
 
     // Pass strings instead of numbers
-    let code = v8::String::new(&mut tc, "add('not', 'numbers')").unwrap();
-    let script = v8::Script::compile(&mut tc, code, None).unwrap();
-    let result = script.run(&mut tc);
+    let code = v8::String::new(&tc, "add('not', 'numbers')").unwrap();
+    let script = v8::Script::compile(&tc, code, None).unwrap();
+    let result = script.run(&tc);
 
     assert!(result.is_none());
     assert!(tc.has_caught());
@@ -523,13 +526,13 @@ fn test_uint8array_type_error() {
 
     let func = v8::Function::new(&mut tc, sum_bytes_v8).unwrap();
     let global = tc.get_current_context().global(&tc);
-    let key = v8::String::new(&mut tc, "sumBytes").unwrap();
-    global.set(&mut tc, key.into(), func.into());
+    let key = v8::String::new(&tc, "sumBytes").unwrap();
+    global.set(&tc, key.into(), func.into());
 
     // Pass a regular array instead of Uint8Array
-    let code = v8::String::new(&mut tc, "sumBytes([1, 2, 3])").unwrap();
-    let script = v8::Script::compile(&mut tc, code, None).unwrap();
-    let result = script.run(&mut tc);
+    let code = v8::String::new(&tc, "sumBytes([1, 2, 3])").unwrap();
+    let script = v8::Script::compile(&tc, code, None).unwrap();
+    let result = script.run(&tc);
 
     assert!(result.is_none());
     assert!(tc.has_caught());
@@ -629,3 +632,2386 @@ fn test_bool_return() {
     let result = script.run(scope).unwrap();
     assert!(result.is_false());
 }
+
+// ============================================================================
+// Test: Class bindings
+// ============================================================================
+
+#[glue_v8::class]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+#[glue_v8::methods]
+impl Point {
+    #[constructor]
+    fn new(x: f64, y: f64) -> Self {
+        Point { x, y }
+    }
+
+    fn norm(&self) -> f64 {
+        (self.x * self.x + self.y * self.y).sqrt()
+    }
+
+    #[method(name = "moveBy")]
+    fn move_by(&mut self, dx: f64, dy: f64) {
+        self.x += dx;
+        self.y += dy;
+    }
+
+    fn x(&self) -> f64 {
+        self.x
+    }
+}
+
+fn run_class_script<'s>(
+    scope: &mut v8::PinScope<'s, '_>,
+    source: &str,
+) -> Option<v8::Local<'s, v8::Value>> {
+    let class = Point_v8_class_template(scope).get_function(scope).unwrap();
+    let global = scope.get_current_context().global(scope);
+    let key = v8::String::new(scope, "Point").unwrap();
+    global.set(scope, key.into(), class.into());
+
+    let code = v8::String::new(scope, source).unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    script.run(scope)
+}
+
+#[test]
+fn test_class_constructor_and_methods() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let result = run_class_script(scope, "const p = new Point(3, 4); p.norm()").unwrap();
+    assert_eq!(result.number_value(scope).unwrap(), 5.0);
+
+    let result = run_class_script(scope, "p.moveBy(1, 2); p.x()").unwrap();
+    assert_eq!(result.number_value(scope).unwrap(), 4.0);
+
+    let result = run_class_script(scope, "p instanceof Point && Point.name").unwrap();
+    assert_eq!(result.to_rust_string_lossy(scope), "Point");
+}
+
+#[test]
+fn test_class_new_instance_from_rust() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let point = Point_v8_new_instance(scope, Point { x: 6.0, y: 8.0 });
+    let global = scope.get_current_context().global(scope);
+    let key = v8::String::new(scope, "p").unwrap();
+    global.set(scope, key.into(), point.into());
+
+    let result = run_class_script(scope, "p instanceof Point ? p.norm() : -1").unwrap();
+    assert_eq!(result.number_value(scope).unwrap(), 10.0);
+
+    let cell = Point_v8_unwrap(scope, point).unwrap();
+    assert_eq!(cell.borrow().x, 6.0);
+}
+
+#[test]
+fn test_class_unwrap_foreign_internal_fields() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    // Another embedder's object, with raw (non-brand) data in both internal fields
+    let template = v8::ObjectTemplate::new(scope);
+    template.set_internal_field_count(2);
+    let foreign = template.new_instance(scope).unwrap();
+    foreign.set_aligned_pointer_in_internal_field(0, 0x1000 as *const std::ffi::c_void, 0);
+    foreign.set_aligned_pointer_in_internal_field(1, 0x2000 as *const std::ffi::c_void, 0);
+    assert!(Point_v8_unwrap(scope, foreign).is_none());
+
+    // An object with internal fields never set
+    let blank = template.new_instance(scope).unwrap();
+    assert!(Point_v8_unwrap(scope, blank).is_none());
+
+    let global = scope.get_current_context().global(scope);
+    let key = v8::String::new(scope, "foreign").unwrap();
+    global.set(scope, key.into(), foreign.into());
+    let result = run_class_script(
+        scope,
+        "try { Point.prototype.norm.call(foreign) } catch (e) { `${e.constructor.name}: ${e.message}` }",
+    )
+    .unwrap();
+    assert_eq!(
+        result.to_rust_string_lossy(scope),
+        "TypeError: Illegal invocation"
+    );
+}
+
+#[test]
+fn test_class_illegal_invocation() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+    let tc = pin!(v8::TryCatch::new(scope));
+    let mut tc = tc.init();
+
+    // Method called on a foreign receiver
+    let result = run_class_script(&mut tc, "Point.prototype.norm.call({})");
+    assert!(result.is_none());
+    assert!(tc.has_caught());
+    let exception = tc.exception().unwrap();
+    assert!(exception.is_native_error());
+    tc.reset();
+
+    // Constructor called without `new`
+    let result = run_class_script(&mut tc, "Point(1, 2)");
+    assert!(result.is_none());
+    let exception = tc.exception().unwrap();
+    let msg = exception.to_rust_string_lossy(&tc);
+    assert!(msg.contains("without 'new'"));
+}
+
+#[glue_v8::class]
+struct FileHandle {
+    closed: Rc<std::cell::Cell<bool>>,
+}
+
+impl Drop for FileHandle {
+    fn drop(&mut self) {
+        self.closed.set(true);
+    }
+}
+
+#[glue_v8::methods]
+impl FileHandle {
+    #[method(name = "isOpen")]
+    fn is_open(&self) -> bool {
+        !self.closed.get()
+    }
+}
+
+#[test]
+fn test_class_instance_dropped_on_isolate_dispose() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let closed = Rc::new(std::cell::Cell::new(false));
+
+    let instance = {
+        let scope = pin!(v8::HandleScope::new(&mut isolate));
+        let mut scope = scope.init();
+        let context = v8::Context::new(&scope, Default::default());
+        let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+        let handle = FileHandle_v8_new_instance(
+            scope,
+            FileHandle {
+                closed: closed.clone(),
+            },
+        );
+        let global = scope.get_current_context().global(scope);
+        let key = v8::String::new(scope, "handle").unwrap();
+        global.set(scope, key.into(), handle.into());
+
+        let code = v8::String::new(scope, "handle.isOpen()").unwrap();
+        let script = v8::Script::compile(scope, code, None).unwrap();
+        assert!(script.run(scope).unwrap().is_true());
+        v8::Global::new(scope, handle)
+    };
+
+    // Still referenced: garbage collection keeps the instance
+    isolate.request_garbage_collection_for_testing(v8::GarbageCollectionType::Full);
+    assert!(!closed.get());
+
+    // Never collected, but released when the isolate is disposed
+    drop(instance);
+    drop(isolate);
+    assert!(closed.get());
+}
+
+#[test]
+fn test_class_wrap_object_without_internal_fields() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    // Nothing is written to the object, and the value is dropped right away
+    let closed = Rc::new(std::cell::Cell::new(false));
+    let object = v8::Object::new(scope);
+    let wrapped = FileHandle_v8_wrap(
+        scope,
+        object,
+        FileHandle {
+            closed: closed.clone(),
+        },
+    );
+    assert!(!wrapped);
+    assert!(closed.get());
+    assert!(FileHandle_v8_unwrap(scope, object).is_none());
+}
+
+// ============================================================================
+// Test: Accessor properties (getter/setter)
+// ============================================================================
+
+#[glue_v8::getter]
+fn user_agent() -> String {
+    "OpenWorkers/1.0".to_string()
+}
+
+struct RequestState {
+    url: std::cell::RefCell<String>,
+}
+
+#[glue_v8::getter(state = Rc<RequestState>, setter = set_url)]
+fn url(state: &Rc<RequestState>) -> String {
+    state.url.borrow().clone()
+}
+
+#[glue_v8::setter(state = Rc<RequestState>)]
+fn set_url(state: &Rc<RequestState>, value: String) -> Result<(), String> {
+    if !value.starts_with("https://") {
+        return Err(format!("Invalid URL: {}", value));
+    }
+
+    *state.url.borrow_mut() = value;
+    Ok(())
+}
+
+#[glue_v8::getter(state = Rc<RequestState>, setter = set_method)]
+fn method(state: &Rc<RequestState>) -> String {
+    if state.url.borrow().is_empty() {
+        "HEAD".to_string()
+    } else {
+        "GET".to_string()
+    }
+}
+
+#[glue_v8::setter]
+fn set_method(value: String) -> Result<(), String> {
+    Err(format!("Cannot set method to {}: read-only", value))
+}
+
+#[test]
+fn test_getter_without_state() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let navigator = v8::Object::new(scope);
+    let key = v8::String::new(scope, "userAgent").unwrap();
+    navigator.set_accessor_with_configuration(scope, key.into(), user_agent_v8_accessor());
+    let global = scope.get_current_context().global(scope);
+    let key = v8::String::new(scope, "navigator").unwrap();
+    global.set(scope, key.into(), navigator.into());
+
+    let code = v8::String::new(scope, "navigator.userAgent").unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(result.to_rust_string_lossy(scope), "OpenWorkers/1.0");
+}
+
+#[test]
+fn test_getter_setter_with_state() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let state = Rc::new(RequestState {
+        url: std::cell::RefCell::new("https://example.com/".to_string()),
+    });
+
+    let template = v8::ObjectTemplate::new(scope);
+    let key = v8::String::new(scope, "url").unwrap();
+    let config = url_v8_accessor(scope, &state);
+    template.set_accessor_with_configuration(key.into(), config);
+    let request = template.new_instance(scope).unwrap();
+    let global = scope.get_current_context().global(scope);
+    let key = v8::String::new(scope, "request").unwrap();
+    global.set(scope, key.into(), request.into());
+
+    let code = v8::String::new(
+        scope,
+        "request.url = 'https://openworkers.com/'; request.url",
+    )
+    .unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(
+        result.to_rust_string_lossy(scope),
+        "https://openworkers.com/"
+    );
+    assert_eq!(*state.url.borrow(), "https://openworkers.com/");
+
+    // Err from the setter throws, leaving the state untouched
+    let tc = pin!(v8::TryCatch::new(scope));
+    let tc = tc.init();
+    let code = v8::String::new(&tc, "request.url = 'ftp://example.com/'").unwrap();
+    let script = v8::Script::compile(&tc, code, None).unwrap();
+    let result = script.run(&tc);
+
+    assert!(result.is_none());
+    assert!(tc.has_caught());
+    let exception = tc.exception().unwrap();
+    let msg = exception.to_rust_string_lossy(&tc);
+    assert!(msg.contains("Invalid URL"), "Got: {}", msg);
+    assert_eq!(*state.url.borrow(), "https://openworkers.com/");
+}
+
+#[test]
+fn test_getter_with_state_and_stateless_setter() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let state = Rc::new(RequestState {
+        url: std::cell::RefCell::new("https://example.com/".to_string()),
+    });
+
+    let template = v8::ObjectTemplate::new(scope);
+    let key = v8::String::new(scope, "method").unwrap();
+    let config = method_v8_accessor(scope, &state);
+    template.set_accessor_with_configuration(key.into(), config);
+    let request = template.new_instance(scope).unwrap();
+    let global = scope.get_current_context().global(scope);
+    let key = v8::String::new(scope, "request").unwrap();
+    global.set(scope, key.into(), request.into());
+
+    let code = v8::String::new(scope, "request.method").unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(result.to_rust_string_lossy(scope), "GET");
+
+    let tc = pin!(v8::TryCatch::new(scope));
+    let tc = tc.init();
+    let code = v8::String::new(&tc, "request.method = 'POST'").unwrap();
+    let script = v8::Script::compile(&tc, code, None).unwrap();
+    let result = script.run(&tc);
+
+    assert!(result.is_none());
+    let exception = tc.exception().unwrap();
+    let msg = exception.to_rust_string_lossy(&tc);
+    assert!(msg.contains("read-only"), "Got: {}", msg);
+}
+
+// ============================================================================
+// Test: Async ops (spawned futures, drained on the event loop)
+// ============================================================================
+
+type LocalTask = std::pin::Pin<Box<dyn std::future::Future<Output = ()>>>;
+
+thread_local! {
+    static LOCAL_TASKS: std::cell::RefCell<Vec<LocalTask>> = const { std::cell::RefCell::new(Vec::new()) };
+}
+
+/// Spawner trait of the embedder, implemented by the executors async ops use
+trait Spawner {
+    fn spawn(task: LocalTask);
+}
+
+/// Minimal single-threaded executor: tasks are queued here and polled by `run_local_tasks`
+struct LocalSpawner;
+
+impl Spawner for LocalSpawner {
+    fn spawn(task: LocalTask) {
+        LOCAL_TASKS.with(|tasks| tasks.borrow_mut().push(task));
+    }
+}
+
+fn run_local_tasks() {
+    let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+
+    loop {
+        let tasks = LOCAL_TASKS.with(|tasks| std::mem::take(&mut *tasks.borrow_mut()));
+        if tasks.is_empty() {
+            break;
+        }
+
+        let pending: Vec<_> = tasks
+            .into_iter()
+            .filter_map(|mut task| task.as_mut().poll(&mut cx).is_pending().then_some(task))
+            .collect();
+        LOCAL_TASKS.with(|tasks| tasks.borrow_mut().extend(pending));
+    }
+}
+
+/// Future that is pending on its first poll, so ops can't complete synchronously
+struct YieldOnce(bool);
+
+impl std::future::Future for YieldOnce {
+    type Output = ();
+
+    fn poll(
+        mut self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<()> {
+        if self.0 {
+            std::task::Poll::Ready(())
+        } else {
+            self.0 = true;
+            std::task::Poll::Pending
+        }
+    }
+}
+
+#[glue_v8::method(promise, spawn = LocalSpawner)]
+async fn async_sqrt(value: f64) -> Result<f64, String> {
+    YieldOnce(false).await;
+
+    if value < 0.0 {
+        Err("negative input".to_string())
+    } else {
+        Ok(value.sqrt())
+    }
+}
+
+#[glue_v8::method(promise, spawn = LocalSpawner)]
+async fn async_repeat(text: String, count: u32) -> String {
+    YieldOnce(false).await;
+    text.repeat(count as usize)
+}
+
+#[test]
+fn test_async_op_resolves_after_drain() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let func = v8::Function::new(scope, async_sqrt_v8).unwrap();
+    let global = scope.get_current_context().global(scope);
+    let key = v8::String::new(scope, "asyncSqrt").unwrap();
+    global.set(scope, key.into(), func.into());
+
+    let code = v8::String::new(scope, "asyncSqrt(16)").unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+
+    let promise: v8::Local<v8::Promise> = result.try_into().unwrap();
+    assert_eq!(promise.state(), v8::PromiseState::Pending);
+
+    // Nothing completed before the executor runs
+    assert_eq!(glue_v8::drain!(scope), 0);
+    assert_eq!(promise.state(), v8::PromiseState::Pending);
+
+    run_local_tasks();
+    assert_eq!(glue_v8::drain!(scope), 1);
+    assert_eq!(promise.state(), v8::PromiseState::Fulfilled);
+    assert_eq!(promise.result(scope).number_value(scope).unwrap(), 4.0);
+}
+
+#[test]
+fn test_async_op_rejects() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let func = v8::Function::new(scope, async_sqrt_v8).unwrap();
+    let global = scope.get_current_context().global(scope);
+    let key = v8::String::new(scope, "asyncSqrt").unwrap();
+    global.set(scope, key.into(), func.into());
+
+    let code = v8::String::new(scope, "asyncSqrt(-1)").unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    let promise: v8::Local<v8::Promise> = result.try_into().unwrap();
+
+    run_local_tasks();
+    assert_eq!(glue_v8::drain!(scope), 1);
+    assert_eq!(promise.state(), v8::PromiseState::Rejected);
+
+    let reason = promise.result(scope).to_rust_string_lossy(scope);
+    assert!(reason.contains("negative input"));
+}
+
+#[test]
+fn test_drain_settles_every_async_op() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let global = scope.get_current_context().global(scope);
+    let func = v8::Function::new(scope, async_sqrt_v8).unwrap();
+    let key = v8::String::new(scope, "asyncSqrt").unwrap();
+    global.set(scope, key.into(), func.into());
+    let func = v8::Function::new(scope, async_repeat_v8).unwrap();
+    let key = v8::String::new(scope, "asyncRepeat").unwrap();
+    global.set(scope, key.into(), func.into());
+
+    let code =
+        v8::String::new(scope, "[asyncSqrt(9), asyncRepeat('ab', 2), asyncSqrt(-1)]").unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result: v8::Local<v8::Array> = script.run(scope).unwrap().try_into().unwrap();
+    let promises: Vec<v8::Local<v8::Promise>> = (0..3)
+        .map(|i| result.get_index(scope, i).unwrap().try_into().unwrap())
+        .collect();
+
+    // One drain settles the calls of both ops
+    run_local_tasks();
+    assert_eq!(glue_v8::drain!(scope), 3);
+    assert_eq!(glue_v8::drain!(scope), 0);
+
+    assert_eq!(promises[0].state(), v8::PromiseState::Fulfilled);
+    assert_eq!(promises[0].result(scope).number_value(scope).unwrap(), 3.0);
+    assert_eq!(promises[1].state(), v8::PromiseState::Fulfilled);
+    assert_eq!(
+        promises[1].result(scope).to_rust_string_lossy(scope),
+        "abab"
+    );
+    assert_eq!(promises[2].state(), v8::PromiseState::Rejected);
+}
+
+// ============================================================================
+// Test: TypeScript declarations
+// ============================================================================
+
+#[glue_v8::method]
+fn find_header(r#type: Option<String>, name: String) -> Option<String> {
+    r#type.map(|ty| format!("{}: {}", ty, name))
+}
+
+#[glue_v8::method]
+fn in_range(value: f64, r#in: Vec<f64>, r#default: bool) -> bool {
+    match (r#in.first(), r#in.last()) {
+        (Some(min), Some(max)) => value >= *min && value <= *max,
+        _ => r#default,
+    }
+}
+
+#[test]
+fn test_dts_declarations() {
+    assert_eq!(
+        ADD_V8_DTS,
+        "declare function add(a: number, b: number): number;"
+    );
+    assert_eq!(
+        SET_VALUE_V8_DTS,
+        "declare function set_value(val: number): void;"
+    );
+    assert_eq!(
+        GREET_V8_DTS,
+        "declare function greet(name: string, title?: string): string;"
+    );
+    assert_eq!(
+        PARSE_NUMBER_V8_DTS,
+        "declare function parse_number(input: string): number;"
+    );
+    assert_eq!(
+        CALL_TWICE_V8_DTS,
+        "declare function call_twice(callback: Function, value: number): number;"
+    );
+    assert_eq!(
+        SUM_BYTES_V8_DTS,
+        "declare function sum_bytes(data: Uint8Array): number;"
+    );
+    assert_eq!(
+        ASYNC_DIVIDE_V8_DTS,
+        "declare function async_divide(a: number, b: number): Promise<number>;"
+    );
+    assert_eq!(
+        ASYNC_SQRT_V8_DTS,
+        "declare function async_sqrt(value: number): Promise<number>;"
+    );
+    // `None` converts to null, and raw identifiers lose their `r#` prefix
+    assert_eq!(
+        FIND_HEADER_V8_DTS,
+        "declare function find_header(type: string | null | undefined, name: string): string | null;"
+    );
+    // Reserved words are not valid parameter names
+    assert_eq!(
+        IN_RANGE_V8_DTS,
+        "declare function in_range(value: number, in_: number[], default_: boolean): boolean;"
+    );
+}
+
+#[test]
+fn test_dts_collection() {
+    let dts = glue_v8::dts!(add, greet);
+
+    assert_eq!(
+        dts,
+        "declare function add(a: number, b: number): number;\n\
+         declare function greet(name: string, title?: string): string;\n"
+    );
+}
+
+// ============================================================================
+// Test: JS names, function length and install helpers
+// ============================================================================
+
+#[glue_v8::method(name = "greetUser")]
+fn greet_user(name: String, greeting: Option<String>) -> String {
+    format!(
+        "{}, {}!",
+        greeting.unwrap_or_else(|| "Hello".to_string()),
+        name
+    )
+}
+
+#[test]
+fn test_install_uses_js_name() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let global = scope.get_current_context().global(scope);
+    greet_user_v8_install(scope, global).unwrap();
+    add_v8_install(scope, global).unwrap();
+
+    let code = v8::String::new(
+        scope,
+        "[greetUser('Ada'), greetUser.name, greetUser.length, add.name, add.length].join('|')",
+    )
+    .unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(
+        result.to_rust_string_lossy(scope),
+        "Hello, Ada!|greetUser|1|add|2"
+    );
+}
+
+#[test]
+fn test_install_with_state() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let counter = Rc::new(Counter {
+        value: std::cell::Cell::new(1),
+    });
+    let ops = v8::Object::new(scope);
+    increment_v8_install(scope, ops, &counter).unwrap();
+    let global = scope.get_current_context().global(scope);
+    let key = v8::String::new(scope, "ops").unwrap();
+    global.set(scope, key.into(), ops.into());
+
+    let code = v8::String::new(scope, "ops.increment(2) + ops.increment.length").unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(result.number_value(scope).unwrap(), 4.0);
+    assert_eq!(counter.value.get(), 3);
+}
+
+// ============================================================================
+// Test: Snapshot external references
+// ============================================================================
+
+#[test]
+fn test_external_refs_snapshot_roundtrip() {
+    init_v8();
+    let refs = glue_v8::external_refs!(add, greet_user);
+    assert_eq!(
+        refs.len(),
+        ADD_V8_EXTERNAL_REFS.len() + GREET_USER_V8_EXTERNAL_REFS.len()
+    );
+
+    // Create a snapshot whose default context has the ops installed
+    let startup_data = {
+        let mut creator = v8::Isolate::snapshot_creator(Some(refs.clone().into()), None);
+        {
+            let scope = pin!(v8::HandleScope::new(&mut creator));
+            let mut scope = scope.init();
+            let context = v8::Context::new(&scope, Default::default());
+            let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+            let global = context.global(scope);
+            add_v8_install(scope, global).unwrap();
+            greet_user_v8_install(scope, global).unwrap();
+            scope.set_default_context(context);
+        }
+        creator
+            .create_blob(v8::FunctionCodeHandling::Clear)
+            .unwrap()
+    };
+
+    // Deserialize it with the same references and call the ops
+    let params = v8::CreateParams::default()
+        .snapshot_blob(startup_data)
+        .external_references(refs.into());
+    let mut isolate = v8::Isolate::new(params);
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let code = v8::String::new(scope, "greetUser('Snapshot', 'Hi') + ' ' + add(1, 2)").unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(result.to_rust_string_lossy(scope), "Hi, Snapshot! 3");
+}
+
+#[test]
+fn test_external_refs_snapshot_class_and_accessor() {
+    init_v8();
+    let refs = glue_v8::external_refs!(Point, user_agent);
+    assert_eq!(
+        refs.len(),
+        POINT_V8_EXTERNAL_REFS.len() + USER_AGENT_V8_EXTERNAL_REFS.len()
+    );
+
+    // Snapshot a context with the class and an accessor property (no instances)
+    let startup_data = {
+        let mut creator = v8::Isolate::snapshot_creator(Some(refs.clone().into()), None);
+        {
+            let scope = pin!(v8::HandleScope::new(&mut creator));
+            let mut scope = scope.init();
+            let context = v8::Context::new(&scope, Default::default());
+            let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+            let global = context.global(scope);
+            let class = Point_v8_class_template(scope).get_function(scope).unwrap();
+            let key = v8::String::new(scope, "Point").unwrap();
+            global.set(scope, key.into(), class.into());
+
+            let template = v8::ObjectTemplate::new(scope);
+            let key = v8::String::new(scope, "userAgent").unwrap();
+            template.set_accessor_with_configuration(key.into(), user_agent_v8_accessor());
+            let navigator = template.new_instance(scope).unwrap();
+            let key = v8::String::new(scope, "navigator").unwrap();
+            global.set(scope, key.into(), navigator.into());
+            scope.set_default_context(context);
+        }
+        creator
+            .create_blob(v8::FunctionCodeHandling::Clear)
+            .unwrap()
+    };
+
+    let params = v8::CreateParams::default()
+        .snapshot_blob(startup_data)
+        .external_references(refs.into());
+    let mut isolate = v8::Isolate::new(params);
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let code = v8::String::new(
+        scope,
+        "const p = new Point(3, 4); p.moveBy(3, 4); p.norm() + ' ' + navigator.userAgent",
+    )
+    .unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(result.to_rust_string_lossy(scope), "10 OpenWorkers/1.0");
+
+    // Calling the constructor without `new` still throws after deserialization
+    let code = v8::String::new(scope, "try { Point(1, 2) } catch (e) { e.name }").unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(result.to_rust_string_lossy(scope), "TypeError");
+}
+
+// ============================================================================
+// Test: Snapshot-safe state (isolate slot, re-bound after deserialization)
+// ============================================================================
+
+#[glue_v8::method(state = Rc<Counter>, snapshot)]
+fn bump(state: &Rc<Counter>, amount: i32) -> i32 {
+    let new_val = state.value.get() + amount;
+    state.value.set(new_val);
+    new_val
+}
+
+#[test]
+fn test_snapshot_state_rebound_after_deserialization() {
+    init_v8();
+    let refs = glue_v8::external_refs!(bump);
+
+    let startup_data = {
+        let mut creator = v8::Isolate::snapshot_creator(Some(refs.clone().into()), None);
+        {
+            let scope = pin!(v8::HandleScope::new(&mut creator));
+            let mut scope = scope.init();
+            let context = v8::Context::new(&scope, Default::default());
+            let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+            // State bound while creating the snapshot is not serialized
+            scope.set_slot(Rc::new(Counter {
+                value: std::cell::Cell::new(100),
+            }));
+            let global = context.global(scope);
+            bump_v8_install(scope, global).unwrap();
+            scope.set_default_context(context);
+        }
+        creator
+            .create_blob(v8::FunctionCodeHandling::Clear)
+            .unwrap()
+    };
+
+    let params = v8::CreateParams::default()
+        .snapshot_blob(startup_data)
+        .external_references(refs.into());
+    let mut isolate = v8::Isolate::new(params);
+    let counter = Rc::new(Counter {
+        value: std::cell::Cell::new(1),
+    });
+
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    // Not bound yet: throws instead of reading a stale pointer
+    {
+        let tc = pin!(v8::TryCatch::new(scope));
+        let tc = tc.init();
+        let code = v8::String::new(&tc, "bump(1)").unwrap();
+        let script = v8::Script::compile(&tc, code, None).unwrap();
+        assert!(script.run(&tc).is_none());
+        let msg = tc.exception().unwrap().to_rust_string_lossy(&tc);
+        assert!(msg.contains("state not bound"), "Got: {}", msg);
+    }
+
+    scope.set_slot(counter.clone());
+    let code = v8::String::new(scope, "bump(2)").unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(result.number_value(scope).unwrap(), 3.0);
+    assert_eq!(counter.value.get(), 3);
+}
+
+#[glue_v8::method(fast(require), state = Rc<Counter>, snapshot)]
+fn fast_snapshot_bump(state: &Rc<Counter>, amount: i32) -> i32 {
+    let new_val = state.value.get() + amount;
+    state.value.set(new_val);
+    new_val
+}
+
+#[test]
+fn test_fast_snapshot_op_after_deserialization() {
+    init_v8();
+    let refs = glue_v8::external_refs!(fast_snapshot_bump);
+
+    // The slow callback, then the fast function and its type info
+    assert_eq!(refs.len(), 3);
+    let fast_call = &FAST_SNAPSHOT_BUMP_V8_FAST_CALL;
+    // SAFETY: the entries are written with these union fields
+    unsafe {
+        assert_eq!(
+            refs[1].pointer,
+            fast_call.address() as *mut std::ffi::c_void
+        );
+        assert!(std::ptr::eq(refs[2].type_info, fast_call.type_info()));
+    }
+
+    let startup_data = {
+        let mut creator = v8::Isolate::snapshot_creator(Some(refs.clone().into()), None);
+        {
+            let scope = pin!(v8::HandleScope::new(&mut creator));
+            let mut scope = scope.init();
+            let context = v8::Context::new(&scope, Default::default());
+            let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+            let global = context.global(scope);
+            fast_snapshot_bump_v8_install(scope, global).unwrap();
+            scope.set_default_context(context);
+        }
+        creator
+            .create_blob(v8::FunctionCodeHandling::Clear)
+            .unwrap()
+    };
+
+    let params = v8::CreateParams::default()
+        .snapshot_blob(startup_data)
+        .external_references(refs.into());
+    let mut isolate = v8::Isolate::new(params);
+    let counter = Rc::new(Counter {
+        value: std::cell::Cell::new(0),
+    });
+    isolate.set_slot(counter.clone());
+
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+    #[cfg(feature = "fast-counters")]
+    let fast_calls = FAST_SNAPSHOT_BUMP_V8_FAST_CALLS.load(std::sync::atomic::Ordering::Relaxed);
+
+    // The restored function keeps its fast overload
+    let code = v8::String::new(
+        scope,
+        r#"
+        function run() {
+            return fast_snapshot_bump(2);
+        }
+        %PrepareFunctionForOptimization(run);
+        run();
+        %OptimizeFunctionOnNextCall(run);
+        run()
+        "#,
+    )
+    .unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(result.number_value(scope).unwrap(), 4.0);
+    assert_eq!(counter.value.get(), 4);
+    #[cfg(feature = "fast-counters")]
+    assert!(
+        FAST_SNAPSHOT_BUMP_V8_FAST_CALLS.load(std::sync::atomic::Ordering::Relaxed) > fast_calls
+    );
+}
+
+// ============================================================================
+// Test: Owned state (released when V8 collects the function)
+// ============================================================================
+
+struct Tracked {
+    value: i32,
+    dropped: Rc<std::cell::Cell<bool>>,
+}
+
+impl Drop for Tracked {
+    fn drop(&mut self) {
+        self.dropped.set(true);
+    }
+}
+
+#[glue_v8::method(state = Rc<Tracked>, owned)]
+fn tracked_value(state: &Rc<Tracked>) -> i32 {
+    state.value
+}
+
+#[test]
+fn test_owned_state_dropped_after_gc() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let dropped = Rc::new(std::cell::Cell::new(false));
+
+    {
+        let scope = pin!(v8::HandleScope::new(&mut isolate));
+        let mut scope = scope.init();
+        let context = v8::Context::new(&scope, Default::default());
+        let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+        let state = Rc::new(Tracked {
+            value: 42,
+            dropped: dropped.clone(),
+        });
+        let func = tracked_value_v8_function(scope, state).unwrap();
+
+        // The function keeps the state alive on its own
+        let recv = v8::undefined(scope).into();
+        let result = func.call(scope, recv, &[]).unwrap();
+        assert_eq!(result.number_value(scope).unwrap(), 42.0);
+        assert!(!dropped.get());
+    }
+
+    // No handle references the function anymore
+    isolate.request_garbage_collection_for_testing(v8::GarbageCollectionType::Full);
+    assert!(dropped.get());
+}
+
+#[glue_v8::method(fast, state = Rc<Tracked>, owned)]
+fn fast_tracked_offset(state: &Rc<Tracked>, by: i32) -> i32 {
+    state.value + by
+}
+
+#[test]
+fn test_owned_state_dropped_on_isolate_dispose() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let dropped = Rc::new(std::cell::Cell::new(false));
+    let fast_dropped = Rc::new(std::cell::Cell::new(false));
+    let state = Rc::new(Tracked {
+        value: 42,
+        dropped: dropped.clone(),
+    });
+    let fast_state = Rc::new(Tracked {
+        value: 10,
+        dropped: fast_dropped.clone(),
+    });
+
+    let context = {
+        let scope = pin!(v8::HandleScope::new(&mut isolate));
+        let mut scope = scope.init();
+        let context = v8::Context::new(&scope, Default::default());
+        let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+        let global = scope.get_current_context().global(scope);
+        let template = tracked_value_v8_template(scope, state.clone());
+        let func = template.get_function(scope).unwrap();
+        let key = v8::String::new(scope, "tracked_value").unwrap();
+        global.set(scope, key.into(), func.into()).unwrap();
+        fast_tracked_offset_v8_install(scope, global, fast_state.clone()).unwrap();
+        #[cfg(feature = "fast-counters")]
+        let fast_calls =
+            FAST_TRACKED_OFFSET_V8_FAST_CALLS.load(std::sync::atomic::Ordering::Relaxed);
+
+        let code = v8::String::new(
+            scope,
+            r#"
+            function run() {
+                return fast_tracked_offset(5);
+            }
+            %PrepareFunctionForOptimization(run);
+            run();
+            %OptimizeFunctionOnNextCall(run);
+            tracked_value() + run()
+            "#,
+        )
+        .unwrap();
+        let script = v8::Script::compile(scope, code, None).unwrap();
+        let result = script.run(scope).unwrap();
+        assert_eq!(result.number_value(scope).unwrap(), 57.0);
+        #[cfg(feature = "fast-counters")]
+        assert!(
+            FAST_TRACKED_OFFSET_V8_FAST_CALLS.load(std::sync::atomic::Ordering::Relaxed)
+                > fast_calls
+        );
+
+        v8::Global::new(scope, context)
+    };
+
+    // The functions are still reachable from the context, so GC keeps the state
+    isolate.request_garbage_collection_for_testing(v8::GarbageCollectionType::Full);
+    assert_eq!(Rc::strong_count(&state), 2);
+    assert_eq!(Rc::strong_count(&fast_state), 2);
+
+    drop(context);
+    drop(isolate);
+    assert_eq!(Rc::strong_count(&state), 1);
+    assert_eq!(Rc::strong_count(&fast_state), 1);
+    drop(state);
+    drop(fast_state);
+    assert!(dropped.get());
+    assert!(fast_dropped.get());
+}
+
+// ============================================================================
+// Test: Arc, &'static and plain state types
+// ============================================================================
+
+struct SharedConfig {
+    limit: i32,
+}
+
+#[glue_v8::method(fast, state = Arc<SharedConfig>)]
+fn clamp_to_limit(state: &Arc<SharedConfig>, value: i32) -> i32 {
+    value.min(state.limit)
+}
+
+#[glue_v8::method(state = Arc<SharedConfig>, owned)]
+fn owned_limit(state: &Arc<SharedConfig>) -> i32 {
+    state.limit
+}
+
+static DEFAULT_CONFIG: SharedConfig = SharedConfig { limit: 7 };
+
+#[glue_v8::method(state = &'static SharedConfig)]
+fn default_limit(state: &SharedConfig) -> i32 {
+    state.limit
+}
+
+#[glue_v8::method(state = Tracked)]
+fn plain_tracked_value(state: &Tracked) -> i32 {
+    state.value
+}
+
+#[test]
+fn test_arc_and_static_state() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let config = Arc::new(SharedConfig { limit: 10 });
+    let global = scope.get_current_context().global(scope);
+    clamp_to_limit_v8_install(scope, global, &config).unwrap();
+    default_limit_v8_install(scope, global, &DEFAULT_CONFIG).unwrap();
+
+    let code = v8::String::new(
+        scope,
+        "let sum = 0; for (let i = 0; i < 100; i++) sum += clamp_to_limit(i); sum + default_limit()",
+    )
+    .unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(
+        result.number_value(scope).unwrap(),
+        (45 + 90 * 10 + 7) as f64
+    );
+
+    // Calls borrow the Arc without leaking or releasing strong counts
+    assert_eq!(Arc::strong_count(&config), 1);
+}
+
+#[test]
+fn test_owned_arc_and_plain_state_dropped_after_gc() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let config = Arc::new(SharedConfig { limit: 3 });
+    let dropped = Rc::new(std::cell::Cell::new(false));
+
+    {
+        let scope = pin!(v8::HandleScope::new(&mut isolate));
+        let mut scope = scope.init();
+        let context = v8::Context::new(&scope, Default::default());
+        let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+        let arc_func = owned_limit_v8_function(scope, config.clone()).unwrap();
+        let plain_func = plain_tracked_value_v8_function(
+            scope,
+            Tracked {
+                value: 42,
+                dropped: dropped.clone(),
+            },
+        )
+        .unwrap();
+
+        let recv = v8::undefined(scope).into();
+        let result = arc_func.call(scope, recv, &[]).unwrap();
+        assert_eq!(result.number_value(scope).unwrap(), 3.0);
+        let result = plain_func.call(scope, recv, &[]).unwrap();
+        assert_eq!(result.number_value(scope).unwrap(), 42.0);
+        assert_eq!(Arc::strong_count(&config), 2);
+        assert!(!dropped.get());
+    }
+
+    isolate.request_garbage_collection_for_testing(v8::GarbageCollectionType::Full);
+    assert_eq!(Arc::strong_count(&config), 1);
+    assert!(dropped.get());
+}
+
+#[test]
+fn test_plain_state_dropped_on_isolate_dispose() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let dropped = Rc::new(std::cell::Cell::new(false));
+
+    let context = {
+        let scope = pin!(v8::HandleScope::new(&mut isolate));
+        let mut scope = scope.init();
+        let context = v8::Context::new(&scope, Default::default());
+        let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+        let global = scope.get_current_context().global(scope);
+        let state = Tracked {
+            value: 42,
+            dropped: dropped.clone(),
+        };
+        plain_tracked_value_v8_install(scope, global, state).unwrap();
+
+        let code = v8::String::new(scope, "plain_tracked_value()").unwrap();
+        let script = v8::Script::compile(scope, code, None).unwrap();
+        let result = script.run(scope).unwrap();
+        assert_eq!(result.number_value(scope).unwrap(), 42.0);
+
+        v8::Global::new(scope, context)
+    };
+
+    // The function is still reachable from the context, so GC keeps the state
+    isolate.request_garbage_collection_for_testing(v8::GarbageCollectionType::Full);
+    assert!(!dropped.get());
+
+    drop(context);
+    drop(isolate);
+    assert!(dropped.get());
+}
+
+// ============================================================================
+// Test: Mutable state borrowed from a RefCell
+// ============================================================================
+
+struct Journal {
+    entries: Vec<String>,
+}
+
+#[glue_v8::method(state = Rc<std::cell::RefCell<Journal>>)]
+fn journal_append(
+    scope: &mut v8::PinScope,
+    state: &mut Journal,
+    entry: String,
+    then: v8::Local<v8::Function>,
+) -> u32 {
+    state.entries.push(entry);
+    let recv = v8::undefined(scope).into();
+    then.call(scope, recv, &[]);
+    state.entries.len() as u32
+}
+
+#[test]
+fn test_mut_state_reentrancy_throws() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let journal = Rc::new(std::cell::RefCell::new(Journal {
+        entries: Vec::new(),
+    }));
+    let global = scope.get_current_context().global(scope);
+    journal_append_v8_install(scope, global, &journal).unwrap();
+
+    let code = v8::String::new(
+        scope,
+        r#"
+        let message;
+        journal_append('a', () => {});
+        const len = journal_append('b', () => {
+            try { journal_append('c', () => {}); } catch (e) { message = e.message; }
+        });
+        `${len}|${message}`
+        "#,
+    )
+    .unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(
+        result.to_rust_string_lossy(scope),
+        "2|op re-entered while state is borrowed"
+    );
+    assert_eq!(journal.borrow().entries, ["a", "b"]);
+}
+
+struct Tally {
+    count: u32,
+}
+
+#[glue_v8::method(fast(require), state = Rc<std::cell::RefCell<Tally>>)]
+fn fast_tally(state: &mut Tally, by: u32) -> u32 {
+    state.count += by;
+    state.count
+}
+
+#[glue_v8::method(state = Rc<std::cell::RefCell<Tally>>)]
+fn tally_while_borrowed(
+    scope: &mut v8::PinScope,
+    state: &mut Tally,
+    then: v8::Local<v8::Function>,
+) -> u32 {
+    let recv = v8::undefined(scope).into();
+    then.call(scope, recv, &[]);
+    state.count
+}
+
+#[test]
+fn test_fast_mut_state() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let tally = Rc::new(std::cell::RefCell::new(Tally { count: 0 }));
+    let global = scope.get_current_context().global(scope);
+    fast_tally_v8_install(scope, global, &tally).unwrap();
+    tally_while_borrowed_v8_install(scope, global, &tally).unwrap();
+    #[cfg(feature = "fast-counters")]
+    let fast_calls = FAST_TALLY_V8_FAST_CALLS.load(std::sync::atomic::Ordering::Relaxed);
+
+    // The optimized fast path mutates the state, and throws when re-entered
+    // while a slow op of the same state holds the borrow
+    let code = v8::String::new(
+        scope,
+        r#"
+        function run() {
+            return fast_tally(2);
+        }
+        %PrepareFunctionForOptimization(run);
+        run();
+        %OptimizeFunctionOnNextCall(run);
+        run();
+        let message;
+        const len = tally_while_borrowed(() => {
+            try { run(); } catch (e) { message = `${e.constructor.name}: ${e.message}`; }
+        });
+        `${len}|${message}|${run()}`
+        "#,
+    )
+    .unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(
+        result.to_rust_string_lossy(scope),
+        "4|Error: op re-entered while state is borrowed|6"
+    );
+    assert_eq!(tally.borrow().count, 6);
+
+    // Every call after optimization, the re-entered one included, is a fast call
+    #[cfg(feature = "fast-counters")]
+    assert!(FAST_TALLY_V8_FAST_CALLS.load(std::sync::atomic::Ordering::Relaxed) - fast_calls >= 3);
+}
+
+// ============================================================================
+// Test: Multiple `#[state]` params resolved from the state registry
+// ============================================================================
+
+struct TimerRegistry {
+    next_id: std::cell::Cell<u32>,
+}
+
+struct Permissions {
+    allow_timers: bool,
+}
+
+#[glue_v8::method]
+fn create_timer(
+    #[state] timers: &TimerRegistry,
+    #[state] perms: &Permissions,
+    delay: u32,
+) -> Result<u32, String> {
+    if !perms.allow_timers {
+        return Err("timers not allowed".to_string());
+    }
+    let id = timers.next_id.get();
+    timers.next_id.set(id + 1);
+    Ok(id * 1000 + delay)
+}
+
+#[test]
+fn test_registry_states() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    // Shared by every context of the isolate
+    isolate.set_slot(Rc::new(Permissions { allow_timers: true }));
+
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let func = v8::Function::new(scope, create_timer_v8).unwrap();
+    let global = scope.get_current_context().global(scope);
+    let key = v8::String::new(scope, "createTimer").unwrap();
+    global.set(scope, key.into(), func.into());
+
+    let code = v8::String::new(
+        scope,
+        "let missing; try { createTimer(5) } catch (e) { missing = e.message } missing",
+    )
+    .unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert!(
+        result
+            .to_rust_string_lossy(scope)
+            .contains("state not registered: TimerRegistry"),
+        "Got: {}",
+        result.to_rust_string_lossy(scope)
+    );
+
+    // Per-context state
+    context.set_slot(Rc::new(TimerRegistry {
+        next_id: std::cell::Cell::new(1),
+    }));
+    let code = v8::String::new(scope, "createTimer(5) + createTimer(7)").unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(result.number_value(scope).unwrap(), (1005 + 2007) as f64);
+}
+
+// ============================================================================
+// Test: Isolate-slot and context-slot state sources
+// ============================================================================
+
+struct RealmInfo {
+    name: String,
+}
+
+#[glue_v8::method(state(slot = context))]
+fn realm_name(state: &Rc<RealmInfo>) -> String {
+    state.name.clone()
+}
+
+#[glue_v8::method(state(slot = isolate))]
+fn isolate_counter(state: &Rc<Counter>) -> i32 {
+    state.value.set(state.value.get() + 1);
+    state.value.get()
+}
+
+fn run_in_realm(scope: &mut v8::PinScope<'_, '_, ()>, name: &str) -> String {
+    let context = v8::Context::new(scope, Default::default());
+    context.set_slot(Rc::new(RealmInfo {
+        name: name.to_string(),
+    }));
+    let scope = &mut v8::ContextScope::new(scope, context);
+
+    // No template plumbing: the wrappers read their state from the slots
+    let global = context.global(scope);
+    let func = v8::Function::new(scope, realm_name_v8).unwrap();
+    let key = v8::String::new(scope, "realmName").unwrap();
+    global.set(scope, key.into(), func.into());
+    let func = v8::Function::new(scope, isolate_counter_v8).unwrap();
+    let key = v8::String::new(scope, "isolateCounter").unwrap();
+    global.set(scope, key.into(), func.into());
+
+    let code = v8::String::new(scope, "`${realmName()}:${isolateCounter()}`").unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    result.to_rust_string_lossy(scope)
+}
+
+#[test]
+fn test_slot_state_sources() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    isolate.set_slot(Rc::new(Counter {
+        value: std::cell::Cell::new(0),
+    }));
+
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+
+    // Each realm has its own context state, the isolate state is shared
+    assert_eq!(run_in_realm(&mut scope, "main"), "main:1");
+    assert_eq!(run_in_realm(&mut scope, "worker"), "worker:2");
+}
+
+#[glue_v8::method(fast(require), state(slot = context))]
+fn fast_realm_len(state: &Rc<RealmInfo>, extra: i32) -> i32 {
+    state.name.len() as i32 + extra
+}
+
+#[glue_v8::method(fast(require), state(slot = isolate))]
+fn fast_isolate_tick(state: &Rc<Counter>, by: i32) -> i32 {
+    state.value.set(state.value.get() + by);
+    state.value.get()
+}
+
+/// Call `fast_realm_len(1)` and `fast_isolate_tick(1)` before and after
+/// optimizing the caller, reporting each result or error message.
+fn run_fast_slot_ops(scope: &mut v8::PinScope) -> String {
+    let code = v8::String::new(
+        scope,
+        r#"
+        (() => {
+            function run() {
+                let realm, tick;
+                try { realm = fast_realm_len(1); } catch (e) { realm = e.message; }
+                try { tick = fast_isolate_tick(1); } catch (e) { tick = e.message; }
+                return `${realm}|${tick}`;
+            }
+            %PrepareFunctionForOptimization(run);
+            const slow = run();
+            %OptimizeFunctionOnNextCall(run);
+            return `${slow}|${run()}`;
+        })()
+        "#,
+    )
+    .unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    result.to_rust_string_lossy(scope)
+}
+
+#[test]
+fn test_fast_slot_state_sources() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let counter = Rc::new(Counter {
+        value: std::cell::Cell::new(0),
+    });
+    isolate.set_slot(counter.clone());
+
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    context.set_slot(Rc::new(RealmInfo {
+        name: "main".to_string(),
+    }));
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let global = context.global(scope);
+    fast_realm_len_v8_install(scope, global).unwrap();
+    fast_isolate_tick_v8_install(scope, global).unwrap();
+    #[cfg(feature = "fast-counters")]
+    let fast_calls = (
+        FAST_REALM_LEN_V8_FAST_CALLS.load(std::sync::atomic::Ordering::Relaxed),
+        FAST_ISOLATE_TICK_V8_FAST_CALLS.load(std::sync::atomic::Ordering::Relaxed),
+    );
+
+    // Both paths resolve the state from the slots
+    assert_eq!(run_fast_slot_ops(scope), "5|1|5|2");
+    assert_eq!(counter.value.get(), 2);
+    #[cfg(feature = "fast-counters")]
+    {
+        use std::sync::atomic::Ordering;
+        assert!(FAST_REALM_LEN_V8_FAST_CALLS.load(Ordering::Relaxed) > fast_calls.0);
+        assert!(FAST_ISOLATE_TICK_V8_FAST_CALLS.load(Ordering::Relaxed) > fast_calls.1);
+    }
+}
+
+#[test]
+fn test_fast_slot_state_missing() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let global = context.global(scope);
+    fast_realm_len_v8_install(scope, global).unwrap();
+    fast_isolate_tick_v8_install(scope, global).unwrap();
+    #[cfg(feature = "fast-counters")]
+    let fast_calls = (
+        FAST_REALM_LEN_V8_FAST_CALLS.load(std::sync::atomic::Ordering::Relaxed),
+        FAST_ISOLATE_TICK_V8_FAST_CALLS.load(std::sync::atomic::Ordering::Relaxed),
+    );
+
+    // Neither slot is set: both paths throw instead of panicking
+    let context_error = "state not bound: set a context slot of type Rc<RealmInfo>";
+    let isolate_error = "state not bound: set an isolate slot of type Rc<Counter>";
+    assert_eq!(
+        run_fast_slot_ops(scope),
+        format!("{context_error}|{isolate_error}|{context_error}|{isolate_error}")
+    );
+    #[cfg(feature = "fast-counters")]
+    {
+        use std::sync::atomic::Ordering;
+        assert!(FAST_REALM_LEN_V8_FAST_CALLS.load(Ordering::Relaxed) > fast_calls.0);
+        assert!(FAST_ISOLATE_TICK_V8_FAST_CALLS.load(Ordering::Relaxed) > fast_calls.1);
+    }
+}
+
+// ============================================================================
+// Test: Param roles detected by type, passed in declaration order
+// ============================================================================
+
+#[glue_v8::method(state = Rc<Counter>)]
+fn add_scaled(
+    amount: i32,
+    counter: &Rc<Counter>,
+    #[scope] cx: &mut v8::PinScope,
+    factor: i32,
+) -> i32 {
+    assert!(cx.get_current_context().global(cx).is_object());
+    counter.value.set(counter.value.get() + amount * factor);
+    counter.value.get()
+}
+
+#[glue_v8::method(fast, state = Rc<Counter>)]
+fn fast_add_scaled(amount: i32, counter: &Rc<Counter>, factor: i32) -> i32 {
+    counter.value.set(counter.value.get() + amount * factor);
+    counter.value.get()
+}
+
+#[glue_v8::method]
+fn describe_isolate(label: String, isolate: &v8::Isolate) -> String {
+    format!("{}: {}", label, isolate.get_slot::<Rc<Counter>>().is_some())
+}
+
+#[test]
+fn test_param_roles_in_declaration_order() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let counter = Rc::new(Counter {
+        value: std::cell::Cell::new(0),
+    });
+    let global = scope.get_current_context().global(scope);
+    add_scaled_v8_install(scope, global, &counter);
+    fast_add_scaled_v8_install(scope, global, &counter);
+    describe_isolate_v8_install(scope, global);
+
+    let code = v8::String::new(
+        scope,
+        "[add_scaled(2, 10), fast_add_scaled(1, 3), describe_isolate('slot'), add_scaled.length].join()",
+    )
+    .unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(result.to_rust_string_lossy(scope), "20,23,slot: false,2");
+}
+
+// ============================================================================
+// Test: Params named like the wrapper bindings
+// ============================================================================
+
+#[glue_v8::method]
+fn shadowing_names(
+    args: String,
+    rv: i32,
+    scope: Option<String>,
+    state: String,
+    resolver: u32,
+) -> String {
+    format!("{}/{}/{:?}/{}/{}", args, rv, scope, state, resolver)
+}
+
+#[glue_v8::method(promise)]
+fn shadowing_promise(promise: i32, result: i32) -> i32 {
+    promise + result
+}
+
+#[glue_v8::method(fast, state = Rc<Counter>)]
+fn shadowing_fast(options: i32, counter: &Rc<Counter>, data: i32) -> i32 {
+    counter.value.get() + options * data
+}
+
+#[test]
+fn test_params_named_like_wrapper_bindings() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let counter = Rc::new(Counter {
+        value: std::cell::Cell::new(1),
+    });
+    let global = scope.get_current_context().global(scope);
+    shadowing_names_v8_install(scope, global);
+    shadowing_promise_v8_install(scope, global);
+    shadowing_fast_v8_install(scope, global, &counter);
+
+    let code = v8::String::new(scope, "shadowing_names('a', 2, 'c', 'd', 5)").unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(result.to_rust_string_lossy(scope), "a/2/Some(\"c\")/d/5");
+
+    let code = v8::String::new(scope, "shadowing_fast(3, 4)").unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(result.number_value(scope).unwrap(), 13.0);
+
+    let code = v8::String::new(scope, "shadowing_promise(3, 4)").unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    let promise = v8::Local::<v8::Promise>::try_from(result).unwrap();
+    assert_eq!(promise.state(), v8::PromiseState::Fulfilled);
+    assert_eq!(promise.result(scope).number_value(scope).unwrap(), 7.0);
+}
+
+// ============================================================================
+// Test: Destructuring patterns and `mut` params
+// ============================================================================
+
+#[derive(serde::Deserialize)]
+struct Size {
+    width: f64,
+    height: f64,
+}
+
+#[glue_v8::method]
+fn scaled_area(
+    _: &mut v8::PinScope,
+    Size { width, height }: Size,
+    (sx, sy): (f64, f64),
+    mut label: String,
+) -> String {
+    label.push_str(": ");
+    label.push_str(&(width * sx * height * sy).to_string());
+    label
+}
+
+#[test]
+fn test_destructuring_params() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let global = scope.get_current_context().global(scope);
+    scaled_area_v8_install(scope, global);
+
+    let code = v8::String::new(
+        scope,
+        "scaled_area({ width: 2, height: 3 }, [2, 0.5], 'area')",
+    )
+    .unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(result.to_rust_string_lossy(scope), "area: 6");
+    assert_eq!(
+        SCALED_AREA_V8_DTS,
+        "declare function scaled_area(arg1: unknown, arg2: [number, number], label: string): string;"
+    );
+}
+
+// ============================================================================
+// Test: `fast(require)` and `{fn}_v8_template` in every mode
+// ============================================================================
+
+#[glue_v8::method(fast(require))]
+fn fast_required_mul(a: i32, b: i32) -> i32 {
+    a * b
+}
+
+// `String` has no Fast API type: falls back to the slow path only
+#[glue_v8::method(fast)]
+fn fast_fallback_repeat(text: String, times: u32) -> String {
+    text.repeat(times as usize)
+}
+
+#[test]
+fn test_template_in_every_mode() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let global = scope.get_current_context().global(scope);
+    let templates = [
+        ("add", add_v8_template(scope)),
+        ("mul", fast_required_mul_v8_template(scope)),
+        ("repeat", fast_fallback_repeat_v8_template(scope)),
+    ];
+    for (name, template) in templates {
+        let func = template.get_function(scope).unwrap();
+        let key = v8::String::new(scope, name).unwrap();
+        global.set(scope, key.into(), func.into());
+    }
+
+    let code = v8::String::new(scope, "[add(2, 3), mul(4, 5), repeat('ab', 2)].join()").unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(result.to_rust_string_lossy(scope), "5,20,abab");
+}
+
+// ============================================================================
+// Test: Fast API string arguments
+// ============================================================================
+
+#[glue_v8::method(fast(require))]
+fn fast_char_count(text: &str) -> u32 {
+    text.chars().count() as u32
+}
+
+struct HeaderMap {
+    names: Vec<String>,
+}
+
+#[glue_v8::method(fast(require), state = Rc<HeaderMap>)]
+fn fast_has_header(state: &Rc<HeaderMap>, name: &str) -> bool {
+    state.names.iter().any(|n| n.eq_ignore_ascii_case(name))
+}
+
+#[test]
+fn test_fast_str_args() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let headers = Rc::new(HeaderMap {
+        names: vec!["Content-Type".to_string()],
+    });
+    let global = scope.get_current_context().global(scope);
+    fast_char_count_v8_install(scope, global);
+    fast_has_header_v8_install(scope, global, &headers);
+
+    // ASCII and Latin-1 strings are one-byte, others take the slow path
+    let code = v8::String::new(
+        scope,
+        "let out = []; for (let i = 0; i < 1000; i++) { out = [fast_char_count('abc'), fast_char_count('café'), fast_char_count('日本'), fast_has_header('content-type'), fast_has_header('accept')]; } out.join()",
+    )
+    .unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(result.to_rust_string_lossy(scope), "3,4,2,true,false");
+    assert_eq!(
+        FAST_CHAR_COUNT_V8_DTS,
+        "declare function fast_char_count(text: string): number;"
+    );
+}
+
+// ============================================================================
+// Test: Fast API ops returning Result
+// ============================================================================
+
+#[glue_v8::method(fast(require))]
+fn fast_checked_div(a: i32, b: i32) -> Result<i32, String> {
+    a.checked_div(b)
+        .ok_or_else(|| format!("cannot divide {} by zero", a))
+}
+
+#[test]
+fn test_fast_result_throws() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let global = scope.get_current_context().global(scope);
+    fast_checked_div_v8_install(scope, global);
+
+    // Same exception on the slow path (first call) and the optimized fast path
+    let code = v8::String::new(
+        scope,
+        r#"
+        function div(a, b) {
+            try {
+                return fast_checked_div(a, b);
+            } catch (e) {
+                return `${e.constructor.name}: ${e.message}`;
+            }
+        }
+        %PrepareFunctionForOptimization(div);
+        const slow = [div(7, 2), div(7, 0)];
+        %OptimizeFunctionOnNextCall(div);
+        [...slow, div(9, 3)].join('|')
+        "#,
+    )
+    .unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(
+        result.to_rust_string_lossy(scope),
+        "3|Error: cannot divide 7 by zero|3"
+    );
+
+    // The throwing call itself takes the fast path
+    #[cfg(feature = "fast-counters")]
+    let fast_calls = FAST_CHECKED_DIV_V8_FAST_CALLS.load(std::sync::atomic::Ordering::Relaxed);
+    let code = v8::String::new(scope, "div(9, 0)").unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(
+        result.to_rust_string_lossy(scope),
+        "Error: cannot divide 9 by zero"
+    );
+    #[cfg(feature = "fast-counters")]
+    assert!(FAST_CHECKED_DIV_V8_FAST_CALLS.load(std::sync::atomic::Ordering::Relaxed) > fast_calls);
+}
+
+// ============================================================================
+// Test: Fast API overloads for trailing `Option` params
+// ============================================================================
+
+#[glue_v8::method(fast(require))]
+fn fast_round(value: f64, digits: Option<i32>) -> f64 {
+    let factor = 10f64.powi(digits.unwrap_or(0));
+    (value * factor).round() / factor
+}
+
+#[glue_v8::method(fast(require), state = Rc<Counter>)]
+fn fast_bump(state: &Rc<Counter>, by: Option<i32>) -> i32 {
+    state.value.set(state.value.get() + by.unwrap_or(1));
+    state.value.get()
+}
+
+#[test]
+fn test_fast_overloads_by_arity() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let counter = Rc::new(Counter {
+        value: std::cell::Cell::new(0),
+    });
+    let global = scope.get_current_context().global(scope);
+    fast_round_v8_install(scope, global);
+    fast_bump_v8_install(scope, global, &counter);
+
+    let code = v8::String::new(
+        scope,
+        r#"
+        function run() {
+            return [fast_round(2.345), fast_round(2.345, 2), fast_bump(), fast_bump(10)];
+        }
+        %PrepareFunctionForOptimization(run);
+        const slow = run();
+        %OptimizeFunctionOnNextCall(run);
+        [...slow, ...run(), fast_round.length, fast_bump.length].join()
+        "#,
+    )
+    .unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(
+        result.to_rust_string_lossy(scope),
+        "2,2.35,1,11,2,2.35,12,22,0,0"
+    );
+
+    // Slow callback, plus a fast function and CFunctionInfo per arity
+    assert_eq!(FAST_ROUND_V8_EXTERNAL_REFS.len(), 5);
+    let _ = (FAST_ROUND_V8_FAST_CALL, FAST_ROUND_V8_FAST_CALL_1);
+}
+
+// ============================================================================
+// Test: 64-bit integers as Number or BigInt (`int64 = ..`)
+// ============================================================================
+
+// Without `int64`, serde_v8 converts them: Numbers are truncated, BigInts accepted
+// (slow path only: `fast` needs an explicit `int64`)
+#[glue_v8::method]
+fn add_u64(a: u64, b: u64) -> u64 {
+    a.wrapping_add(b)
+}
+
+#[glue_v8::method(fast, int64 = "number")]
+fn add_u64_number(a: u64, b: u64) -> u64 {
+    a.wrapping_add(b)
+}
+
+#[glue_v8::method(fast, int64 = "bigint")]
+fn add_i64_bigint(a: i64, b: Option<i64>) -> i64 {
+    a.wrapping_add(b.unwrap_or(1))
+}
+
+#[test]
+fn test_int64_representation() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let global = scope.get_current_context().global(scope);
+    add_u64_v8_install(scope, global);
+    add_u64_number_v8_install(scope, global);
+    add_i64_bigint_v8_install(scope, global);
+
+    // Same results and errors before and after optimization (Fast API calls)
+    let code = v8::String::new(
+        scope,
+        r#"
+        function attempt(f) {
+            try {
+                const value = f();
+                return `${typeof value} ${value}`;
+            } catch (e) {
+                return `${e.constructor.name}: ${e.message}`;
+            }
+        }
+        function run() {
+            return [
+                attempt(() => add_u64(2, 3)),
+                attempt(() => add_u64(1.5, 2)),
+                attempt(() => add_u64(1n, 2)),
+                attempt(() => add_u64_number(2, 3)),
+                attempt(() => add_u64_number(2 ** 52, 2 ** 52)),
+                attempt(() => add_u64_number(-1, 0)),
+                attempt(() => add_u64_number(1.5, 2)),
+                attempt(() => add_u64_number(1n, 2)),
+                attempt(() => add_i64_bigint(2n, -5n)),
+                attempt(() => add_i64_bigint(2n)),
+                attempt(() => add_i64_bigint(2, 1n)),
+                attempt(() => add_i64_bigint(2n ** 63n, 0n)),
+            ];
+        }
+        %PrepareFunctionForOptimization(run);
+        const slow = run();
+        %OptimizeFunctionOnNextCall(run);
+        const fast = run();
+        JSON.stringify(slow) === JSON.stringify(fast) ? slow.join('|') : `${slow} != ${fast}`
+        "#,
+    )
+    .unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(
+        result.to_rust_string_lossy(scope),
+        [
+            "number 5",
+            "number 3",
+            "number 3",
+            "number 5",
+            "RangeError: return value is not a safe integer",
+            "RangeError: argument 0: expected a non-negative safe integer",
+            "RangeError: argument 0: expected a non-negative safe integer",
+            "TypeError: argument 0: expected a number",
+            "bigint -3",
+            "bigint 3",
+            "TypeError: argument 0: expected a BigInt",
+            "RangeError: argument 0: BigInt out of range for i64",
+        ]
+        .join("|")
+    );
+    // serde_v8 returns a BigInt beyond `Number.MAX_SAFE_INTEGER`
+    assert_eq!(
+        ADD_U64_V8_DTS,
+        "declare function add_u64(a: number, b: number): number | bigint;"
+    );
+    assert_eq!(
+        ADD_I64_BIGINT_V8_DTS,
+        "declare function add_i64_bigint(a: bigint, b?: bigint): bigint;"
+    );
+}
+
+// ============================================================================
+// Test: Fast API class methods
+// ============================================================================
+
+#[glue_v8::class]
+struct Cursor {
+    pos: u32,
+}
+
+#[glue_v8::methods]
+impl Cursor {
+    #[constructor]
+    fn new() -> Self {
+        Cursor { pos: 0 }
+    }
+
+    #[method(fast(require))]
+    fn advance(&mut self, by: Option<u32>) -> u32 {
+        self.pos += by.unwrap_or(1);
+        self.pos
+    }
+
+    #[method(fast(require), name = "isAt")]
+    fn is_at(&self, pos: u32) -> bool {
+        self.pos == pos
+    }
+}
+
+#[test]
+fn test_fast_class_methods() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let global = scope.get_current_context().global(scope);
+    let cursor = Cursor_v8_class_template(scope).get_function(scope).unwrap();
+    let key = v8::String::new(scope, "Cursor").unwrap();
+    global.set(scope, key.into(), cursor.into());
+
+    // Same results before and after optimization, and the same TypeError for
+    // foreign receivers (plain objects and other classes)
+    let result = run_class_script(
+        scope,
+        r#"
+        function attempt(f) {
+            try {
+                return String(f());
+            } catch (e) {
+                return `${e.constructor.name}: ${e.message}`;
+            }
+        }
+        function run(c) {
+            return [
+                attempt(() => c.advance()),
+                attempt(() => c.advance(2)),
+                attempt(() => c.isAt(3)),
+                attempt(() => Cursor.prototype.advance.call({}, 1)),
+                attempt(() => Cursor.prototype.isAt.call(new Point(0, 0), 0)),
+            ];
+        }
+        %PrepareFunctionForOptimization(run);
+        const slow = run(new Cursor());
+        %OptimizeFunctionOnNextCall(run);
+        const fast = run(new Cursor());
+        JSON.stringify(slow) === JSON.stringify(fast) ? slow.join('|') : `${slow} != ${fast}`
+        "#,
+    )
+    .unwrap();
+    assert_eq!(
+        result.to_rust_string_lossy(scope),
+        "1|3|true|TypeError: Illegal invocation|TypeError: Illegal invocation"
+    );
+}
+
+// ============================================================================
+// Test: Direct primitive conversions (serde_v8 as fallback)
+// ============================================================================
+
+#[glue_v8::method]
+fn primitive_echo(flag: bool, count: i32, size: u32, ratio: f32, name: String) -> String {
+    format!("{} {} {} {} {}", flag, count, size, ratio, name)
+}
+
+#[glue_v8::method]
+fn primitive_u32_max() -> u32 {
+    u32::MAX
+}
+
+#[glue_v8::method]
+fn primitive_half(value: f32) -> f32 {
+    value / 2.0
+}
+
+#[glue_v8::method(promise)]
+fn primitive_not(value: Option<bool>) -> bool {
+    !value.unwrap_or(false)
+}
+
+#[test]
+fn test_primitive_conversions() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let global = scope.get_current_context().global(scope);
+    primitive_echo_v8_install(scope, global);
+    primitive_u32_max_v8_install(scope, global);
+    primitive_half_v8_install(scope, global);
+    primitive_not_v8_install(scope, global);
+
+    // Numbers wrap like JS ToInt32/ToUint32, bools follow truthiness, BigInts
+    // and String objects go through serde_v8
+    let code = v8::String::new(
+        scope,
+        r#"
+        let error;
+        try {
+            primitive_echo(true, 'x', 0, 0, '');
+        } catch (e) {
+            error = `${e.constructor.name}: ${e.message}`;
+        }
+        [
+            primitive_echo(true, -3.7, 2 ** 32 - 1, 0.5, 'héllo'),
+            primitive_echo(1, 2n, 3, 1n, new String('boxed')),
+            primitive_echo('', 2 ** 32 + 5, -1, 0, 'wrap'),
+            typeof primitive_u32_max(),
+            primitive_u32_max(),
+            primitive_half(3),
+            error.startsWith('TypeError: argument 1: expected i32: '),
+        ].join('|')
+        "#,
+    )
+    .unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(
+        result.to_rust_string_lossy(scope),
+        "true -3 4294967295 0.5 héllo|true 2 3 1 boxed|false 5 4294967295 0 wrap|number|4294967295|1.5|true"
+    );
+
+    let code = v8::String::new(scope, "primitive_not()").unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let promise = v8::Local::<v8::Promise>::try_from(script.run(scope).unwrap()).unwrap();
+    assert!(promise.result(scope).is_true());
+}
+
+// ============================================================================
+// Test: Return values that fail to convert throw (or reject)
+// ============================================================================
+
+/// Fails to serialize, like a map with non-string keys
+struct Unserializable;
+
+impl serde::Serialize for Unserializable {
+    fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+        Err(serde::ser::Error::custom("not serializable"))
+    }
+}
+
+#[glue_v8::method]
+fn broken_value() -> Unserializable {
+    Unserializable
+}
+
+#[glue_v8::method(promise, name = "brokenValueAsync")]
+fn broken_value_promise() -> Result<Unserializable, String> {
+    Ok(Unserializable)
+}
+
+#[test]
+fn test_return_conversion_errors() {
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let global = scope.get_current_context().global(scope);
+    broken_value_v8_install(scope, global);
+    broken_value_promise_v8_install(scope, global);
+
+    let code = v8::String::new(
+        scope,
+        "try { broken_value(); 'no error' } catch (e) { `${e.constructor.name}: ${e.message}` }",
+    )
+    .unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let result = script.run(scope).unwrap();
+    assert_eq!(
+        result.to_rust_string_lossy(scope),
+        "TypeError: broken_value: failed to convert the return value: not serializable"
+    );
+
+    // The promise is rejected instead of staying pending
+    let code = v8::String::new(scope, "brokenValueAsync()").unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    let promise = v8::Local::<v8::Promise>::try_from(script.run(scope).unwrap()).unwrap();
+    assert_eq!(promise.state(), v8::PromiseState::Rejected);
+    assert_eq!(
+        promise.result(scope).to_rust_string_lossy(scope),
+        "TypeError: brokenValueAsync: failed to convert the return value: not serializable"
+    );
+}
+
+// ============================================================================
+// Test: every `fast` op takes its Fast API path (`fast-counters` feature)
+// ============================================================================
+
+/// Call `expr` from a function optimized with `%OptimizeFunctionOnNextCall`,
+/// and assert that the op counted by `fast_calls` took its Fast API path.
+#[cfg(feature = "fast-counters")]
+fn assert_fast_path(
+    scope: &mut v8::PinScope,
+    expr: &str,
+    fast_calls: &std::sync::atomic::AtomicUsize,
+) {
+    use std::sync::atomic::Ordering;
+
+    let before = fast_calls.load(Ordering::Relaxed);
+    let source = format!(
+        r#"
+        (() => {{
+            function hot() {{
+                return {expr};
+            }}
+            %PrepareFunctionForOptimization(hot);
+            for (let i = 0; i < 10; i++) hot();
+            %OptimizeFunctionOnNextCall(hot);
+            for (let i = 0; i < 10; i++) hot();
+        }})()
+        "#
+    );
+    let code = v8::String::new(scope, &source).unwrap();
+    let script = v8::Script::compile(scope, code, None).unwrap();
+    script.run(scope).unwrap();
+
+    assert!(
+        fast_calls.load(Ordering::Relaxed) > before,
+        "`{}` did not take the Fast API path",
+        expr
+    );
+}
+
+#[cfg(feature = "fast-counters")]
+#[test]
+fn test_fast_ops_take_fast_path() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    init_v8();
+    let mut isolate = v8::Isolate::new(v8::CreateParams::default());
+    let scope = pin!(v8::HandleScope::new(&mut isolate));
+    let mut scope = scope.init();
+    let context = v8::Context::new(&scope, Default::default());
+    let scope = &mut v8::ContextScope::new(&mut scope, context);
+
+    let config = Arc::new(SharedConfig { limit: 10 });
+    let counter = Rc::new(Counter {
+        value: std::cell::Cell::new(0),
+    });
+    let headers = Rc::new(HeaderMap {
+        names: vec!["Content-Type".to_string()],
+    });
+    let global = scope.get_current_context().global(scope);
+    clamp_to_limit_v8_install(scope, global, &config).unwrap();
+    fast_add_scaled_v8_install(scope, global, &counter);
+    shadowing_fast_v8_install(scope, global, &counter);
+    fast_required_mul_v8_install(scope, global);
+    fast_char_count_v8_install(scope, global);
+    fast_has_header_v8_install(scope, global, &headers);
+    fast_checked_div_v8_install(scope, global);
+    fast_round_v8_install(scope, global);
+    fast_bump_v8_install(scope, global, &counter);
+    add_u64_number_v8_install(scope, global);
+    add_i64_bigint_v8_install(scope, global);
+    scope.set_slot(counter.clone());
+    context.set_slot(Rc::new(RealmInfo {
+        name: "main".to_string(),
+    }));
+    fast_snapshot_bump_v8_install(scope, global);
+    fast_isolate_tick_v8_install(scope, global);
+    fast_realm_len_v8_install(scope, global);
+    let tracked = Rc::new(Tracked {
+        value: 1,
+        dropped: Rc::new(std::cell::Cell::new(false)),
+    });
+    fast_tracked_offset_v8_install(scope, global, tracked);
+    let tally = Rc::new(std::cell::RefCell::new(Tally { count: 0 }));
+    fast_tally_v8_install(scope, global, &tally);
+    run_class_script(scope, "globalThis.cursor = new Cursor()");
+
+    let cases: &[(&str, &AtomicUsize)] = &[
+        ("clamp_to_limit(42)", &CLAMP_TO_LIMIT_V8_FAST_CALLS),
+        ("fast_add_scaled(1, 2)", &FAST_ADD_SCALED_V8_FAST_CALLS),
+        ("shadowing_fast(3, 4)", &SHADOWING_FAST_V8_FAST_CALLS),
+        ("fast_required_mul(6, 7)", &FAST_REQUIRED_MUL_V8_FAST_CALLS),
+        ("fast_char_count('abc')", &FAST_CHAR_COUNT_V8_FAST_CALLS),
+        ("fast_has_header('accept')", &FAST_HAS_HEADER_V8_FAST_CALLS),
+        ("fast_checked_div(7, 2)", &FAST_CHECKED_DIV_V8_FAST_CALLS),
+        ("fast_round(2.345)", &FAST_ROUND_V8_FAST_CALLS_1),
+        ("fast_round(2.345, 2)", &FAST_ROUND_V8_FAST_CALLS),
+        ("fast_bump()", &FAST_BUMP_V8_FAST_CALLS_0),
+        ("fast_bump(1)", &FAST_BUMP_V8_FAST_CALLS),
+        ("add_u64_number(2, 3)", &ADD_U64_NUMBER_V8_FAST_CALLS),
+        ("add_i64_bigint(2n)", &ADD_I64_BIGINT_V8_FAST_CALLS_1),
+        ("add_i64_bigint(2n, 1n)", &ADD_I64_BIGINT_V8_FAST_CALLS),
+        ("fast_snapshot_bump(1)", &FAST_SNAPSHOT_BUMP_V8_FAST_CALLS),
+        ("fast_isolate_tick(1)", &FAST_ISOLATE_TICK_V8_FAST_CALLS),
+        ("fast_realm_len(1)", &FAST_REALM_LEN_V8_FAST_CALLS),
+        ("fast_tracked_offset(1)", &FAST_TRACKED_OFFSET_V8_FAST_CALLS),
+        ("fast_tally(1)", &FAST_TALLY_V8_FAST_CALLS),
+        ("cursor.advance(1)", &CURSOR_ADVANCE_V8_FAST_CALLS),
+        ("cursor.isAt(0)", &CURSOR_IS_AT_V8_FAST_CALLS),
+    ];
+    for (expr, fast_calls) in cases {
+        assert_fast_path(scope, expr, fast_calls);
+    }
+
+    // Ops without a Fast API path only count slow calls
+    fast_fallback_repeat_v8_install(scope, global);
+    let code = v8::String::new(scope, "fast_fallback_repeat('ab', 2)").unwrap();
+    v8::Script::compile(scope, code, None)
+        .unwrap()
+        .run(scope)
+        .unwrap();
+    assert!(FAST_FALLBACK_REPEAT_V8_SLOW_CALLS.load(Ordering::Relaxed) > 0);
+    assert_eq!(
+        FAST_FALLBACK_REPEAT_V8_FAST_CALLS.load(Ordering::Relaxed),
+        0
+    );
+}
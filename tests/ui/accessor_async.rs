@@ -0,0 +1,6 @@
+#[glue_v8::getter]
+async fn user_agent() -> String {
+    "OpenWorkers/1.0".to_string()
+}
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[glue_v8::setter]
+fn set_title(scope: &mut v8::PinScope, isolate: &mut v8::Isolate, value: String) {}
+
+fn main() {}
@@ -0,0 +1,21 @@
+use std::rc::Rc;
+
+struct RequestState {
+    url: std::cell::RefCell<String>,
+}
+
+struct ResponseState {
+    status: std::cell::Cell<u16>,
+}
+
+#[glue_v8::getter(state = Rc<RequestState>, setter = set_url)]
+fn url(state: &Rc<RequestState>) -> String {
+    state.url.borrow().clone()
+}
+
+#[glue_v8::setter(state = Rc<ResponseState>)]
+fn set_url(state: &Rc<ResponseState>, value: u16) {
+    state.status.set(value);
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+struct RequestState {
+    url: String,
+}
+
+#[glue_v8::getter(state = std::rc::Rc<RequestState>, fast)]
+fn url(state: &std::rc::Rc<RequestState>) -> String {
+    state.url.clone()
+}
+
+#[glue_v8::setter(state(slot = isolate) = std::rc::Rc<RequestState>)]
+fn set_url(state: &std::rc::Rc<RequestState>, value: String) {}
+
+fn main() {}
@@ -0,0 +1,21 @@
+struct Limits {
+    max: u32,
+}
+
+#[glue_v8::method(promise, spawn = NoopSpawner, state = Limits)]
+async fn plain_state(state: &Limits) -> u32 {
+    state.max
+}
+
+#[glue_v8::method(promise, spawn = NoopSpawner)]
+async fn borrowed_registry_state(#[state] limits: &Limits) -> u32 {
+    limits.max
+}
+
+struct NoopSpawner;
+
+impl NoopSpawner {
+    fn spawn(_task: std::pin::Pin<Box<dyn std::future::Future<Output = ()>>>) {}
+}
+
+fn main() {}
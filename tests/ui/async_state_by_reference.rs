@@ -0,0 +1,16 @@
+struct Limits {
+    max: u32,
+}
+
+#[glue_v8::method(promise, spawn = NoopSpawner, state = std::rc::Rc<Limits>)]
+async fn max_limit(state: &std::rc::Rc<Limits>) -> u32 {
+    state.max
+}
+
+struct NoopSpawner;
+
+impl NoopSpawner {
+    fn spawn(_task: std::pin::Pin<Box<dyn std::future::Future<Output = ()>>>) {}
+}
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[glue_v8::method(promise)]
+async fn sleep(ms: u32) {}
+
+fn main() {}
@@ -0,0 +1,6 @@
+struct Timers;
+
+#[glue_v8::method]
+fn clear(#[state] timers: &mut Timers, id: u32) {}
+
+fn main() {}
@@ -0,0 +1,14 @@
+#[glue_v8::class]
+struct Point {
+    x: f64,
+}
+
+#[glue_v8::methods]
+impl Point {
+    #[method(state = u32)]
+    fn x(&self) -> f64 {
+        self.x
+    }
+}
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[glue_v8::method]
+fn twice(a: &mut v8::PinScope, b: &mut v8::PinScope) {}
+
+fn main() {}
@@ -0,0 +1,14 @@
+#[glue_v8::class]
+struct Label {
+    text: String,
+}
+
+#[glue_v8::methods]
+impl Label {
+    #[method(fast(require))]
+    fn text(&self) -> String {
+        self.text.clone()
+    }
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+#[glue_v8::method(fast(require))]
+fn pad(width: Option<i32>, fill: i32) -> i32 {
+    width.unwrap_or(0) * fill
+}
+
+fn main() {}
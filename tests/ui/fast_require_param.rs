@@ -0,0 +1,6 @@
+#[glue_v8::method(fast(require))]
+fn greet(name: String, times: u32) -> u32 {
+    name.len() as u32 * times
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+#[glue_v8::method(fast(require))]
+fn label(n: i32) -> String {
+    n.to_string()
+}
+
+fn main() {}
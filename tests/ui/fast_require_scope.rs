@@ -0,0 +1,7 @@
+#[glue_v8::method(fast(require))]
+fn now(scope: &mut v8::PinScope) -> f64 {
+    let _ = scope;
+    0.0
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+#[glue_v8::method(fast(strict))]
+fn add(a: i32, b: i32) -> i32 {
+    a + b
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+#[glue_v8::method(fast)]
+fn hrtime_ns() -> u64 {
+    0
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+#[glue_v8::getter]
+fn user_agent(version: u32) -> String {
+    format!("OpenWorkers/{}", version)
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+#[glue_v8::method(name = "foo-bar")]
+fn foo_bar() {}
+
+#[glue_v8::method(name = "delete")]
+fn delete_entry() {}
+
+#[glue_v8::method("")]
+fn unnamed() {}
+
+#[glue_v8::class(name = "Text Decoder")]
+struct TextDecoder;
+
+fn main() {}
@@ -0,0 +1,8 @@
+struct Counter;
+
+#[glue_v8::method(state = )]
+fn count(state: &std::rc::Rc<Counter>) -> u32 {
+    0
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+struct Counter {
+    value: u32,
+}
+
+#[glue_v8::method(state = std::rc::Rc<Counter>)]
+fn bump(state: &mut Counter) -> u32 {
+    state.value += 1;
+    state.value
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+struct Limits {
+    max: u32,
+}
+
+#[glue_v8::method(state = Limits, snapshot)]
+fn max(state: &Limits) -> u32 {
+    state.max
+}
+
+fn main() {}
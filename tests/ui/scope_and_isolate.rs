@@ -0,0 +1,4 @@
+#[glue_v8::method]
+fn both(scope: &mut v8::PinScope, isolate: &v8::Isolate) {}
+
+fn main() {}
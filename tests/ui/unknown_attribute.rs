@@ -0,0 +1,6 @@
+#[glue_v8::method(promis)]
+fn add(a: f64, b: f64) -> f64 {
+    a + b
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+#[glue_v8::method(int64 = "string")]
+fn id(value: u64) -> u64 {
+    value
+}
+
+fn main() {}
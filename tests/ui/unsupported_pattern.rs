@@ -0,0 +1,4 @@
+#[glue_v8::method]
+fn literal(1: i32) {}
+
+fn main() {}